@@ -1,3 +1,4 @@
+use futures::StreamExt;
 use mindat_rs::{GeomaterialsQuery, ImaMineralsQuery, LocalitiesQuery, MindatClient};
 use serde::Serialize;
 use std::error::Error;
@@ -384,54 +385,41 @@ async fn search_localities_by_gps(
     );
 
     // Fetch multiple pages to get more results
-    const MAX_PAGES: i32 = 50; // Fetch up to 500 localities
-    let mut all_results = Vec::new();
-    let mut current_page: i32 = 1;
-    let mut truncated = false;
-
-    loop {
-        let mut page_query = LocalitiesQuery::new().page(current_page);
-        if let Some(ref c) = country {
-            if !c.is_empty() {
-                page_query = page_query.country(c);
-            }
+    const MAX_PAGES: usize = 50; // Fetch up to 500 localities
+    let mut query = LocalitiesQuery::new();
+    if let Some(ref c) = country {
+        if !c.is_empty() {
+            query = query.country(c);
         }
-        if let Some(ref n) = name_contains {
-            if !n.is_empty() {
-                page_query = page_query.name_contains(n);
-            }
+    }
+    if let Some(ref n) = name_contains {
+        if !n.is_empty() {
+            query = query.name_contains(n);
         }
+    }
+
+    let mut localities = client.localities_stream(query).max_pages(MAX_PAGES);
+    let mut all_results = Vec::new();
+    let mut error = None;
 
-        debug_log!("Fetching page {}...", current_page);
-        let response = client.localities(page_query).await;
-        match &response {
-            Ok(r) => debug_log!(
-                "Page {} returned {} localities",
-                current_page,
-                r.results.len()
-            ),
+    while let Some(result) = localities.next().await {
+        match result {
+            Ok(loc) => all_results.push(loc),
             Err(e) => {
-                debug_log!("Page {} failed: {}", current_page, e);
+                debug_log!("Page {} failed: {}", localities.pages_fetched() + 1, e);
+                error = Some(e.to_string());
                 break;
             }
         }
+    }
 
-        let response = response?;
-        let next_page = response.next_page();
-        all_results.extend(response.results);
-
-        // Check for next page
-        if let Some(page) = next_page {
-            if page > MAX_PAGES {
-                debug_log!("Reached max pages limit ({}), results truncated", MAX_PAGES);
-                truncated = true;
-                break;
-            }
-            current_page = page;
-        } else {
-            debug_log!("No more pages after page {}", current_page);
-            break;
-        }
+    // Without an error, anything left to resume from means we hit MAX_PAGES
+    let truncated = error.is_none() && localities.resume_url().is_some();
+    let fetched_pages = localities.pages_fetched();
+    if truncated {
+        debug_log!("Reached max pages limit ({}), results truncated", MAX_PAGES);
+    } else if error.is_none() {
+        debug_log!("No more pages after page {}", fetched_pages);
     }
 
     debug_log!(
@@ -462,11 +450,12 @@ async fn search_localities_by_gps(
         }
     );
 
-    // Return results with truncated flag
+    // Return results with truncated flag, and the error that stopped the search early
     let result = serde_json::json!({
         "results": filtered,
         "truncated": truncated,
-        "fetched_pages": current_page,
+        "error": error,
+        "fetched_pages": fetched_pages,
         "next": null,
         "previous": null
     });
@@ -0,0 +1,420 @@
+//! Endpoint-level interface to the Mindat API.
+//!
+//! [`MindatApi`] covers the endpoint methods of
+//! [`MindatClient`](crate::MindatClient), so code that only needs the data can
+//! take `&dyn MindatApi` or `impl MindatApi` instead of the client itself.
+//! Tests can then pass a hand-written fake, and applications can wrap the
+//! client in decorators or swap in an offline implementation.
+//!
+//! Methods return boxed futures, which keeps the trait usable as a trait
+//! object. Streams, batch fetches and the generic
+//! [`request`](crate::MindatClient::request) methods are built on the HTTP
+//! client and stay on [`MindatClient`](crate::MindatClient).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use mindat_rs::{MindatApi, MindatClient, Result};
+//!
+//! /// Looks up a mineral name through any implementation of the API.
+//! async fn mineral_name(api: &dyn MindatApi, id: i32) -> Result<Option<String>> {
+//!     Ok(api.geomaterial(id).await?.name)
+//! }
+//!
+//! # async fn example() -> Result<()> {
+//! let client = MindatClient::new("your-token");
+//! println!("{:?}", mineral_name(&client, 3337).await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+use crate::client::MindatClient;
+use crate::error::Result;
+use crate::models::*;
+
+/// The endpoints of the Mindat API.
+///
+/// Implemented by [`MindatClient`](crate::MindatClient), whose methods of the
+/// same name document each endpoint, and by `&T`, `Box<T>` and `Arc<T>` for
+/// any implementation `T`.
+pub trait MindatApi: Send + Sync {
+    /// List all countries.
+    fn countries(&self) -> BoxFuture<'_, Result<PaginatedResponse<Country>>>;
+
+    /// List countries with pagination.
+    fn countries_page(&self, page: i32) -> BoxFuture<'_, Result<PaginatedResponse<Country>>>;
+
+    /// Get a specific country by ID.
+    fn country(&self, id: i32) -> BoxFuture<'_, Result<Country>>;
+
+    /// List geomaterials with optional filters.
+    fn geomaterials(
+        &self,
+        query: GeomaterialsQuery,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<Geomaterial>>>;
+
+    /// Get a specific geomaterial by ID.
+    fn geomaterial(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>>;
+
+    /// Get varieties of a specific geomaterial.
+    fn geomaterial_varieties(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>>;
+
+    /// Search for geomaterials.
+    fn geomaterials_search<'a>(
+        &'a self,
+        q: &'a str,
+        size: Option<i32>,
+    ) -> BoxFuture<'a, Result<Vec<serde_json::Value>>>;
+
+    /// List localities with optional filters.
+    fn localities(
+        &self,
+        query: LocalitiesQuery,
+    ) -> BoxFuture<'_, Result<CursorPaginatedResponse<Locality>>>;
+
+    /// Get a specific locality by ID.
+    fn locality(&self, id: i32) -> BoxFuture<'_, Result<Locality>>;
+
+    /// List locality ages.
+    fn locality_ages(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityAge>>>;
+
+    /// Get a specific locality age by ID.
+    fn locality_age(&self, age_id: i32) -> BoxFuture<'_, Result<LocalityAge>>;
+
+    /// List locality statuses.
+    fn locality_statuses(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityStatus>>>;
+
+    /// Get a specific locality status by ID.
+    fn locality_status(&self, ls_id: i32) -> BoxFuture<'_, Result<LocalityStatus>>;
+
+    /// List locality types.
+    fn locality_types(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityType>>>;
+
+    /// Get a specific locality type by ID.
+    fn locality_type(&self, lt_id: i32) -> BoxFuture<'_, Result<LocalityType>>;
+
+    /// List geographic regions.
+    fn geo_regions(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<serde_json::Value>>>;
+
+    /// List IMA-approved minerals.
+    fn minerals_ima(
+        &self,
+        query: ImaMineralsQuery,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<ImaMaterial>>>;
+
+    /// Get a specific IMA mineral by ID.
+    fn mineral_ima(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>>;
+
+    /// Get Dana 8th edition classification groups.
+    fn dana8_groups(&self) -> BoxFuture<'_, Result<serde_json::Value>>;
+
+    /// Get Dana 8th edition classification subgroups.
+    fn dana8_subgroups(&self) -> BoxFuture<'_, Result<serde_json::Value>>;
+
+    /// Get a specific Dana 8th edition classification.
+    fn dana8(&self, id: i32) -> BoxFuture<'_, Result<serde_json::Value>>;
+
+    /// Get Nickel-Strunz 10th edition classification classes.
+    fn strunz10_classes(&self) -> BoxFuture<'_, Result<serde_json::Value>>;
+
+    /// Get Nickel-Strunz 10th edition classification subclasses.
+    fn strunz10_subclasses(&self) -> BoxFuture<'_, Result<serde_json::Value>>;
+
+    /// Get Nickel-Strunz 10th edition classification families.
+    fn strunz10_families(&self) -> BoxFuture<'_, Result<serde_json::Value>>;
+
+    /// Get a specific Nickel-Strunz 10th edition classification.
+    fn strunz10(&self, id: i32) -> BoxFuture<'_, Result<serde_json::Value>>;
+
+    /// Get photo count statistics.
+    fn photocount(&self) -> BoxFuture<'_, Result<serde_json::Value>>;
+}
+
+// Inherent methods take precedence, so `self.countries()` and friends below
+// call the client's own async methods.
+impl MindatApi for MindatClient {
+    fn countries(&self) -> BoxFuture<'_, Result<PaginatedResponse<Country>>> {
+        Box::pin(self.countries())
+    }
+
+    fn countries_page(&self, page: i32) -> BoxFuture<'_, Result<PaginatedResponse<Country>>> {
+        Box::pin(self.countries_page(page))
+    }
+
+    fn country(&self, id: i32) -> BoxFuture<'_, Result<Country>> {
+        Box::pin(self.country(id))
+    }
+
+    fn geomaterials(
+        &self,
+        query: GeomaterialsQuery,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<Geomaterial>>> {
+        Box::pin(self.geomaterials(query))
+    }
+
+    fn geomaterial(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>> {
+        Box::pin(self.geomaterial(id))
+    }
+
+    fn geomaterial_varieties(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>> {
+        Box::pin(self.geomaterial_varieties(id))
+    }
+
+    fn geomaterials_search<'a>(
+        &'a self,
+        q: &'a str,
+        size: Option<i32>,
+    ) -> BoxFuture<'a, Result<Vec<serde_json::Value>>> {
+        Box::pin(self.geomaterials_search(q, size))
+    }
+
+    fn localities(
+        &self,
+        query: LocalitiesQuery,
+    ) -> BoxFuture<'_, Result<CursorPaginatedResponse<Locality>>> {
+        Box::pin(self.localities(query))
+    }
+
+    fn locality(&self, id: i32) -> BoxFuture<'_, Result<Locality>> {
+        Box::pin(self.locality(id))
+    }
+
+    fn locality_ages(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityAge>>> {
+        Box::pin(self.locality_ages(page))
+    }
+
+    fn locality_age(&self, age_id: i32) -> BoxFuture<'_, Result<LocalityAge>> {
+        Box::pin(self.locality_age(age_id))
+    }
+
+    fn locality_statuses(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityStatus>>> {
+        Box::pin(self.locality_statuses(page))
+    }
+
+    fn locality_status(&self, ls_id: i32) -> BoxFuture<'_, Result<LocalityStatus>> {
+        Box::pin(self.locality_status(ls_id))
+    }
+
+    fn locality_types(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityType>>> {
+        Box::pin(self.locality_types(page))
+    }
+
+    fn locality_type(&self, lt_id: i32) -> BoxFuture<'_, Result<LocalityType>> {
+        Box::pin(self.locality_type(lt_id))
+    }
+
+    fn geo_regions(
+        &self,
+        page: Option<i32>,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<serde_json::Value>>> {
+        Box::pin(self.geo_regions(page))
+    }
+
+    fn minerals_ima(
+        &self,
+        query: ImaMineralsQuery,
+    ) -> BoxFuture<'_, Result<PaginatedResponse<ImaMaterial>>> {
+        Box::pin(self.minerals_ima(query))
+    }
+
+    fn mineral_ima(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>> {
+        Box::pin(self.mineral_ima(id))
+    }
+
+    fn dana8_groups(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.dana8_groups())
+    }
+
+    fn dana8_subgroups(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.dana8_subgroups())
+    }
+
+    fn dana8(&self, id: i32) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.dana8(id))
+    }
+
+    fn strunz10_classes(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.strunz10_classes())
+    }
+
+    fn strunz10_subclasses(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.strunz10_subclasses())
+    }
+
+    fn strunz10_families(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.strunz10_families())
+    }
+
+    fn strunz10(&self, id: i32) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.strunz10(id))
+    }
+
+    fn photocount(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+        Box::pin(self.photocount())
+    }
+}
+
+/// Implements [`MindatApi`] for a pointer type by forwarding every method.
+macro_rules! forward_api {
+    ($($pointer:ty),*) => {$(
+        impl<T: MindatApi + ?Sized> MindatApi for $pointer {
+            fn countries(&self) -> BoxFuture<'_, Result<PaginatedResponse<Country>>> {
+                (**self).countries()
+            }
+
+            fn countries_page(
+                &self,
+                page: i32,
+            ) -> BoxFuture<'_, Result<PaginatedResponse<Country>>> {
+                (**self).countries_page(page)
+            }
+
+            fn country(&self, id: i32) -> BoxFuture<'_, Result<Country>> {
+                (**self).country(id)
+            }
+
+            fn geomaterials(
+                &self,
+                query: GeomaterialsQuery,
+            ) -> BoxFuture<'_, Result<PaginatedResponse<Geomaterial>>> {
+                (**self).geomaterials(query)
+            }
+
+            fn geomaterial(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>> {
+                (**self).geomaterial(id)
+            }
+
+            fn geomaterial_varieties(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>> {
+                (**self).geomaterial_varieties(id)
+            }
+
+            fn geomaterials_search<'a>(
+                &'a self,
+                q: &'a str,
+                size: Option<i32>,
+            ) -> BoxFuture<'a, Result<Vec<serde_json::Value>>> {
+                (**self).geomaterials_search(q, size)
+            }
+
+            fn localities(
+                &self,
+                query: LocalitiesQuery,
+            ) -> BoxFuture<'_, Result<CursorPaginatedResponse<Locality>>> {
+                (**self).localities(query)
+            }
+
+            fn locality(&self, id: i32) -> BoxFuture<'_, Result<Locality>> {
+                (**self).locality(id)
+            }
+
+            fn locality_ages(
+                &self,
+                page: Option<i32>,
+            ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityAge>>> {
+                (**self).locality_ages(page)
+            }
+
+            fn locality_age(&self, age_id: i32) -> BoxFuture<'_, Result<LocalityAge>> {
+                (**self).locality_age(age_id)
+            }
+
+            fn locality_statuses(
+                &self,
+                page: Option<i32>,
+            ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityStatus>>> {
+                (**self).locality_statuses(page)
+            }
+
+            fn locality_status(&self, ls_id: i32) -> BoxFuture<'_, Result<LocalityStatus>> {
+                (**self).locality_status(ls_id)
+            }
+
+            fn locality_types(
+                &self,
+                page: Option<i32>,
+            ) -> BoxFuture<'_, Result<PaginatedResponse<LocalityType>>> {
+                (**self).locality_types(page)
+            }
+
+            fn locality_type(&self, lt_id: i32) -> BoxFuture<'_, Result<LocalityType>> {
+                (**self).locality_type(lt_id)
+            }
+
+            fn geo_regions(
+                &self,
+                page: Option<i32>,
+            ) -> BoxFuture<'_, Result<PaginatedResponse<serde_json::Value>>> {
+                (**self).geo_regions(page)
+            }
+
+            fn minerals_ima(
+                &self,
+                query: ImaMineralsQuery,
+            ) -> BoxFuture<'_, Result<PaginatedResponse<ImaMaterial>>> {
+                (**self).minerals_ima(query)
+            }
+
+            fn mineral_ima(&self, id: i32) -> BoxFuture<'_, Result<Geomaterial>> {
+                (**self).mineral_ima(id)
+            }
+
+            fn dana8_groups(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).dana8_groups()
+            }
+
+            fn dana8_subgroups(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).dana8_subgroups()
+            }
+
+            fn dana8(&self, id: i32) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).dana8(id)
+            }
+
+            fn strunz10_classes(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).strunz10_classes()
+            }
+
+            fn strunz10_subclasses(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).strunz10_subclasses()
+            }
+
+            fn strunz10_families(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).strunz10_families()
+            }
+
+            fn strunz10(&self, id: i32) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).strunz10(id)
+            }
+
+            fn photocount(&self) -> BoxFuture<'_, Result<serde_json::Value>> {
+                (**self).photocount()
+            }
+        }
+    )*};
+}
+
+forward_api!(&T, Box<T>, Arc<T>);
@@ -0,0 +1,102 @@
+//! Fetching many records by ID.
+//!
+//! The `*_by_ids` methods of [`MindatClient`](crate::MindatClient) split the
+//! IDs into chunks of [`CHUNK_SIZE`], fetch each chunk with an `id__in`
+//! filter, and run up to [`CONCURRENCY`] chunks at a time.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example() -> mindat_rs::Result<()> {
+//! use mindat_rs::MindatClient;
+//!
+//! let client = MindatClient::new("your-token");
+//! let quartz = client.geomaterial(3337).await?;
+//!
+//! let batch = client.localities_by_ids(&quartz.locality.unwrap_or_default()).await?;
+//! println!("{} localities, {} not found", batch.results.len(), batch.missing.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::{HashMap, HashSet};
+
+use crate::error::Result;
+use crate::stream::PageStream;
+
+/// Number of IDs fetched per request.
+pub const CHUNK_SIZE: usize = 100;
+
+/// Number of chunks fetched concurrently.
+pub const CONCURRENCY: usize = 4;
+
+/// Records fetched by ID.
+#[derive(Debug, Clone)]
+pub struct BatchResponse<T> {
+    /// The records found, in the order their IDs were given. Each ID appears
+    /// at most once, even if it was requested several times.
+    pub results: Vec<T>,
+    /// IDs for which no record was returned, in the order they were given.
+    pub missing: Vec<i32>,
+}
+
+impl<T> BatchResponse<T> {
+    /// Returns true if a record was found for every ID.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Fetch records for `ids`, using `fetch` to stream the records of one chunk.
+pub(crate) async fn fetch_by_ids<T, F>(
+    ids: &[i32],
+    fetch: F,
+    id_of: fn(&T) -> i32,
+) -> Result<BatchResponse<T>>
+where
+    T: Send + 'static,
+    F: Fn(Vec<i32>) -> PageStream<T>,
+{
+    let mut seen = HashSet::new();
+    let unique: Vec<i32> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+
+    let fetched: Vec<T> = stream::iter(unique.chunks(CHUNK_SIZE))
+        .map(|chunk| fetch(chunk.to_vec()).try_collect::<Vec<T>>())
+        .buffered(CONCURRENCY)
+        .try_concat()
+        .await?;
+
+    Ok(assemble(&unique, fetched, id_of))
+}
+
+/// Order fetched records by `ids` and note the IDs without a record.
+fn assemble<T>(ids: &[i32], fetched: Vec<T>, id_of: fn(&T) -> i32) -> BatchResponse<T> {
+    let mut by_id: HashMap<i32, T> = fetched
+        .into_iter()
+        .map(|record| (id_of(&record), record))
+        .collect();
+
+    let mut results = Vec::with_capacity(by_id.len());
+    let mut missing = Vec::new();
+    for id in ids {
+        match by_id.remove(id) {
+            Some(record) => results.push(record),
+            None => missing.push(*id),
+        }
+    }
+    BatchResponse { results, missing }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble_orders_by_ids() {
+        let batch = assemble(&[3, 1, 4, 5], vec![1, 5, 3, 9], |id| *id);
+        assert_eq!(batch.results, vec![3, 1, 5]);
+        assert_eq!(batch.missing, vec![4]);
+        assert!(!batch.is_complete());
+    }
+}
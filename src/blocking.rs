@@ -0,0 +1,458 @@
+//! Blocking client for the Mindat API.
+//!
+//! Enabled with the `blocking` cargo feature. [`MindatClient`] mirrors the
+//! async [`crate::MindatClient`] with synchronous methods, driving requests on
+//! a small runtime owned by the client, so callers do not need a tokio
+//! runtime of their own.
+//!
+//! The blocking client must not be used from within an async runtime; use the
+//! async client there instead.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use mindat_rs::GeomaterialsQuery;
+//! use mindat_rs::blocking::MindatClient;
+//!
+//! let client = MindatClient::new("your-api-token")?;
+//! let minerals = client.geomaterials(GeomaterialsQuery::new().name("quartz"))?;
+//! for mineral in minerals.results {
+//!     println!("{}: {:?}", mineral.id, mineral.name);
+//! }
+//! # Ok::<(), mindat_rs::MindatError>(())
+//! ```
+
+use futures::StreamExt;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use url::Url;
+
+use crate::batch::BatchResponse;
+use crate::client;
+use crate::credentials::SecretToken;
+use crate::error::Result;
+use crate::models::*;
+use crate::response::Response;
+use crate::stream::{ItemStream, PageStream};
+
+/// Blocking client for interacting with the Mindat API.
+#[derive(Debug, Clone)]
+pub struct MindatClient {
+    inner: client::MindatClient,
+    runtime: Arc<Runtime>,
+}
+
+impl MindatClient {
+    /// Create a new client with the given API token.
+    pub fn new(token: impl Into<SecretToken>) -> Result<Self> {
+        Self::builder().token(token).build_blocking()
+    }
+
+    /// Create a new client without authentication.
+    /// Some endpoints (like minerals_ima) work without authentication.
+    pub fn anonymous() -> Result<Self> {
+        Self::builder().build_blocking()
+    }
+
+    /// Create a client from a profile in the configuration file, or from
+    /// the [`default`](crate::credentials::DEFAULT_PROFILE) profile when
+    /// `name` is `None`.
+    ///
+    /// See [`credentials`](crate::credentials) for the file's location and
+    /// format.
+    pub fn from_profile(name: Option<&str>) -> Result<Self> {
+        Self::from_async(client::MindatClient::from_profile(name)?)
+    }
+
+    /// Create a new client builder for more configuration options.
+    ///
+    /// Finish with [`MindatClientBuilder::build_blocking`](crate::MindatClientBuilder::build_blocking).
+    pub fn builder() -> client::MindatClientBuilder {
+        client::MindatClientBuilder::new()
+    }
+
+    /// Wrap a configured async client.
+    pub fn from_async(inner: client::MindatClient) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Get the underlying async client.
+    pub fn as_async(&self) -> &client::MindatClient {
+        &self.inner
+    }
+
+    /// Set the API token.
+    pub fn set_token(&mut self, token: impl Into<SecretToken>) {
+        self.inner.set_token(token);
+    }
+
+    /// Get the base URL.
+    pub fn base_url(&self) -> &Url {
+        self.inner.base_url()
+    }
+
+    /// Run a future to completion on the client's runtime.
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    // ==================== Countries ====================
+
+    /// List all countries.
+    pub fn countries(&self) -> Result<PaginatedResponse<Country>> {
+        self.block_on(self.inner.countries())
+    }
+
+    /// List countries with pagination.
+    pub fn countries_page(&self, page: i32) -> Result<PaginatedResponse<Country>> {
+        self.block_on(self.inner.countries_page(page))
+    }
+
+    /// Get a specific country by ID.
+    pub fn country(&self, id: i32) -> Result<Country> {
+        self.block_on(self.inner.country(id))
+    }
+
+    // ==================== Geomaterials ====================
+
+    /// List geomaterials with optional filters.
+    pub fn geomaterials(&self, query: GeomaterialsQuery) -> Result<PaginatedResponse<Geomaterial>> {
+        self.block_on(self.inner.geomaterials(query))
+    }
+
+    /// List geomaterials, with the response's metadata.
+    pub fn geomaterials_with_meta(
+        &self,
+        query: GeomaterialsQuery,
+    ) -> Result<Response<PaginatedResponse<Geomaterial>>> {
+        self.block_on(self.inner.geomaterials_with_meta(query))
+    }
+
+    /// Iterate over geomaterials matching the query, following pagination.
+    pub fn geomaterials_iter(&self, query: GeomaterialsQuery) -> PageIter<Geomaterial> {
+        self.page_iter(self.inner.geomaterials_stream(query))
+    }
+
+    /// Iterate over geomaterials matching the query, decoding each result as
+    /// soon as it is received.
+    pub fn geomaterials_items(&self, query: GeomaterialsQuery) -> ItemIter<Geomaterial> {
+        self.item_iter(self.inner.geomaterials_items(query))
+    }
+
+    /// Get a specific geomaterial by ID.
+    pub fn geomaterial(&self, id: i32) -> Result<Geomaterial> {
+        self.block_on(self.inner.geomaterial(id))
+    }
+
+    /// Get a specific geomaterial by ID, with the response's metadata.
+    pub fn geomaterial_with_meta(&self, id: i32) -> Result<Response<Geomaterial>> {
+        self.block_on(self.inner.geomaterial_with_meta(id))
+    }
+
+    /// Get many geomaterials by ID.
+    pub fn geomaterials_by_ids(&self, ids: &[i32]) -> Result<BatchResponse<Geomaterial>> {
+        self.block_on(self.inner.geomaterials_by_ids(ids))
+    }
+
+    /// Get varieties of a specific geomaterial.
+    pub fn geomaterial_varieties(&self, id: i32) -> Result<Geomaterial> {
+        self.block_on(self.inner.geomaterial_varieties(id))
+    }
+
+    /// Search for geomaterials.
+    pub fn geomaterials_search(
+        &self,
+        q: &str,
+        size: Option<i32>,
+    ) -> Result<Vec<serde_json::Value>> {
+        self.block_on(self.inner.geomaterials_search(q, size))
+    }
+
+    // ==================== Localities ====================
+
+    /// List localities with optional filters.
+    pub fn localities(&self, query: LocalitiesQuery) -> Result<CursorPaginatedResponse<Locality>> {
+        self.block_on(self.inner.localities(query))
+    }
+
+    /// List localities, with the response's metadata.
+    pub fn localities_with_meta(
+        &self,
+        query: LocalitiesQuery,
+    ) -> Result<Response<CursorPaginatedResponse<Locality>>> {
+        self.block_on(self.inner.localities_with_meta(query))
+    }
+
+    /// Iterate over localities matching the query, following cursor pagination.
+    pub fn localities_iter(&self, query: LocalitiesQuery) -> PageIter<Locality> {
+        self.page_iter(self.inner.localities_stream(query))
+    }
+
+    /// Iterate over localities matching the query, decoding each result as
+    /// soon as it is received.
+    pub fn localities_items(&self, query: LocalitiesQuery) -> ItemIter<Locality> {
+        self.item_iter(self.inner.localities_items(query))
+    }
+
+    /// Get many localities by ID.
+    pub fn localities_by_ids(&self, ids: &[i32]) -> Result<BatchResponse<Locality>> {
+        self.block_on(self.inner.localities_by_ids(ids))
+    }
+
+    /// Get a specific locality by ID.
+    pub fn locality(&self, id: i32) -> Result<Locality> {
+        self.block_on(self.inner.locality(id))
+    }
+
+    /// Get a specific locality by ID, with the response's metadata.
+    pub fn locality_with_meta(&self, id: i32) -> Result<Response<Locality>> {
+        self.block_on(self.inner.locality_with_meta(id))
+    }
+
+    // ==================== Locality Metadata ====================
+
+    /// List locality ages.
+    pub fn locality_ages(&self, page: Option<i32>) -> Result<PaginatedResponse<LocalityAge>> {
+        self.block_on(self.inner.locality_ages(page))
+    }
+
+    /// Get a specific locality age by ID.
+    pub fn locality_age(&self, age_id: i32) -> Result<LocalityAge> {
+        self.block_on(self.inner.locality_age(age_id))
+    }
+
+    /// List locality statuses.
+    pub fn locality_statuses(
+        &self,
+        page: Option<i32>,
+    ) -> Result<PaginatedResponse<LocalityStatus>> {
+        self.block_on(self.inner.locality_statuses(page))
+    }
+
+    /// Get a specific locality status by ID.
+    pub fn locality_status(&self, ls_id: i32) -> Result<LocalityStatus> {
+        self.block_on(self.inner.locality_status(ls_id))
+    }
+
+    /// List locality types.
+    pub fn locality_types(&self, page: Option<i32>) -> Result<PaginatedResponse<LocalityType>> {
+        self.block_on(self.inner.locality_types(page))
+    }
+
+    /// Get a specific locality type by ID.
+    pub fn locality_type(&self, lt_id: i32) -> Result<LocalityType> {
+        self.block_on(self.inner.locality_type(lt_id))
+    }
+
+    /// List geographic regions.
+    pub fn geo_regions(&self, page: Option<i32>) -> Result<PaginatedResponse<serde_json::Value>> {
+        self.block_on(self.inner.geo_regions(page))
+    }
+
+    // ==================== IMA Minerals ====================
+
+    /// List IMA-approved minerals.
+    pub fn minerals_ima(&self, query: ImaMineralsQuery) -> Result<PaginatedResponse<ImaMaterial>> {
+        self.block_on(self.inner.minerals_ima(query))
+    }
+
+    /// List IMA-approved minerals, with the response's metadata.
+    pub fn minerals_ima_with_meta(
+        &self,
+        query: ImaMineralsQuery,
+    ) -> Result<Response<PaginatedResponse<ImaMaterial>>> {
+        self.block_on(self.inner.minerals_ima_with_meta(query))
+    }
+
+    /// Iterate over IMA minerals matching the query, following pagination.
+    pub fn minerals_ima_iter(&self, query: ImaMineralsQuery) -> PageIter<ImaMaterial> {
+        self.page_iter(self.inner.minerals_ima_stream(query))
+    }
+
+    /// Iterate over IMA minerals matching the query, decoding each result as
+    /// soon as it is received.
+    pub fn minerals_ima_items(&self, query: ImaMineralsQuery) -> ItemIter<ImaMaterial> {
+        self.item_iter(self.inner.minerals_ima_items(query))
+    }
+
+    /// Get many IMA minerals by ID.
+    pub fn minerals_ima_by_ids(&self, ids: &[i32]) -> Result<BatchResponse<ImaMaterial>> {
+        self.block_on(self.inner.minerals_ima_by_ids(ids))
+    }
+
+    /// Get a specific IMA mineral by ID.
+    pub fn mineral_ima(&self, id: i32) -> Result<Geomaterial> {
+        self.block_on(self.inner.mineral_ima(id))
+    }
+
+    // ==================== Classification Systems ====================
+
+    /// Get Dana 8th edition classification groups.
+    pub fn dana8_groups(&self) -> Result<serde_json::Value> {
+        self.block_on(self.inner.dana8_groups())
+    }
+
+    /// Get Dana 8th edition classification subgroups.
+    pub fn dana8_subgroups(&self) -> Result<serde_json::Value> {
+        self.block_on(self.inner.dana8_subgroups())
+    }
+
+    /// Get a specific Dana 8th edition classification.
+    pub fn dana8(&self, id: i32) -> Result<serde_json::Value> {
+        self.block_on(self.inner.dana8(id))
+    }
+
+    /// Get Nickel-Strunz 10th edition classification classes.
+    pub fn strunz10_classes(&self) -> Result<serde_json::Value> {
+        self.block_on(self.inner.strunz10_classes())
+    }
+
+    /// Get Nickel-Strunz 10th edition classification subclasses.
+    pub fn strunz10_subclasses(&self) -> Result<serde_json::Value> {
+        self.block_on(self.inner.strunz10_subclasses())
+    }
+
+    /// Get Nickel-Strunz 10th edition classification families.
+    pub fn strunz10_families(&self) -> Result<serde_json::Value> {
+        self.block_on(self.inner.strunz10_families())
+    }
+
+    /// Get a specific Nickel-Strunz 10th edition classification.
+    pub fn strunz10(&self, id: i32) -> Result<serde_json::Value> {
+        self.block_on(self.inner.strunz10(id))
+    }
+
+    // ==================== Other ====================
+
+    /// Get photo count statistics.
+    pub fn photocount(&self) -> Result<serde_json::Value> {
+        self.block_on(self.inner.photocount())
+    }
+
+    // ==================== Generic Requests ====================
+
+    /// Make a GET request to any endpoint and decode the response as `T`.
+    ///
+    /// See [`MindatClient::request`](crate::MindatClient::request).
+    pub fn request<T, Q>(&self, path: &str, query: &Q) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+        Q: serde::Serialize + ?Sized,
+    {
+        self.block_on(self.inner.request(path, query))
+    }
+
+    /// Make a GET request to any endpoint and decode the response as `T`,
+    /// with the response's metadata.
+    pub fn request_with_meta<T, Q>(&self, path: &str, query: &Q) -> Result<Response<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        Q: serde::Serialize + ?Sized,
+    {
+        self.block_on(self.inner.request_with_meta(path, query))
+    }
+
+    /// Make a GET request to any endpoint and return the JSON response as is.
+    pub fn request_raw<Q>(&self, path: &str, query: &Q) -> Result<serde_json::Value>
+    where
+        Q: serde::Serialize + ?Sized,
+    {
+        self.block_on(self.inner.request_raw(path, query))
+    }
+
+    /// Wrap a page stream in a blocking iterator.
+    fn page_iter<T: Send + 'static>(&self, stream: PageStream<T>) -> PageIter<T> {
+        PageIter {
+            // Pages are only fetched while the iterator is advanced
+            stream: stream.prefetch(0),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Wrap an item stream in a blocking iterator.
+    fn item_iter<T: serde::de::DeserializeOwned>(&self, stream: ItemStream<T>) -> ItemIter<T> {
+        ItemIter {
+            stream,
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+/// Blocking iterator over a paginated endpoint.
+///
+/// The blocking counterpart of [`PageStream`]; see its documentation for
+/// limits and resuming interrupted iterations.
+pub struct PageIter<T> {
+    stream: PageStream<T>,
+    runtime: Arc<Runtime>,
+}
+
+impl<T: Send + 'static> PageIter<T> {
+    /// Stop after yielding at most `max` results.
+    pub fn max_items(mut self, max: usize) -> Self {
+        self.stream = self.stream.max_items(max);
+        self
+    }
+
+    /// Stop after fetching at most `max` pages.
+    pub fn max_pages(mut self, max: usize) -> Self {
+        self.stream = self.stream.max_pages(max);
+        self
+    }
+
+    /// Get the underlying stream, e.g. to read its resume position.
+    pub fn stream(&self) -> &PageStream<T> {
+        &self.stream
+    }
+}
+
+impl<T: Send + 'static> Iterator for PageIter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// Blocking iterator over a paginated endpoint that decodes each result as
+/// soon as it is received.
+///
+/// The blocking counterpart of [`ItemStream`].
+pub struct ItemIter<T> {
+    stream: ItemStream<T>,
+    runtime: Arc<Runtime>,
+}
+
+impl<T: serde::de::DeserializeOwned> ItemIter<T> {
+    /// Stop after yielding at most `max` results.
+    pub fn max_items(mut self, max: usize) -> Self {
+        self.stream = self.stream.max_items(max);
+        self
+    }
+
+    /// Stop after fetching at most `max` pages.
+    pub fn max_pages(mut self, max: usize) -> Self {
+        self.stream = self.stream.max_pages(max);
+        self
+    }
+
+    /// Get the underlying stream, e.g. to read the reported total.
+    pub fn stream(&self) -> &ItemStream<T> {
+        &self.stream
+    }
+}
+
+impl<T: serde::de::DeserializeOwned> Iterator for ItemIter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
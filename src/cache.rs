@@ -0,0 +1,473 @@
+//! In-memory response cache.
+//!
+//! A [`CacheConfig`] configured on the
+//! [`MindatClientBuilder`](crate::MindatClientBuilder) keeps successful
+//! responses in memory, keyed by request URL and the credentials the request
+//! was made with, so reference data such as
+//! locality types or classification tables is not fetched over and over.
+//! The cache is shared by every clone of the resulting client.
+//!
+//! Entries are served without a request until their time-to-live expires.
+//! Expired entries carrying an `ETag` or `Last-Modified` header are then
+//! revalidated with a conditional request; a `304 Not Modified` reply renews
+//! the entry without downloading the body again.
+
+use reqwest::StatusCode;
+use reqwest::header::{
+    ETAG, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use url::Url;
+
+use crate::credentials::Fingerprint;
+use crate::error::HtmlPage;
+use crate::transport::TransportResponse;
+
+/// Default time-to-live of cached responses.
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// Response cache configuration.
+///
+/// # Example
+///
+/// ```no_run
+/// use mindat_rs::{CacheConfig, MindatClient};
+/// use std::time::Duration;
+///
+/// let client = MindatClient::builder()
+///     .token("your-token")
+///     .cache(
+///         CacheConfig::new(1000)
+///             .ttl(Duration::from_secs(60))
+///             .endpoint_ttl("/locality-type/", Duration::from_secs(24 * 3600))
+///             .endpoint_ttl("/geomaterials/", Duration::from_secs(3600)),
+///     )
+///     .build()?;
+/// # Ok::<(), mindat_rs::MindatError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheConfig {
+    max_entries: usize,
+    ttl: Duration,
+    endpoint_ttls: Vec<(String, Duration)>,
+}
+
+impl CacheConfig {
+    /// Cache up to `max_entries` responses, evicting the least recently used
+    /// ones first. Entries live for 5 minutes by default.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            ttl: DEFAULT_TTL,
+            endpoint_ttls: Vec::new(),
+        }
+    }
+
+    /// Set the time-to-live of responses without an endpoint-specific TTL.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the time-to-live of responses from endpoints under `path`, such as
+    /// `"/geomaterials/"`. The longest matching path wins. A zero TTL keeps
+    /// the endpoint out of the cache.
+    pub fn endpoint_ttl(mut self, path: impl Into<String>, ttl: Duration) -> Self {
+        self.endpoint_ttls.push((normalize_path(&path.into()), ttl));
+        self
+    }
+
+    /// Time-to-live for an endpoint path.
+    fn ttl_for(&self, path: &str) -> Duration {
+        self.endpoint_ttls
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.ttl, |(_, ttl)| *ttl)
+    }
+}
+
+/// Counters describing how the cache has been used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Requests answered from the cache without contacting the API.
+    pub hits: u64,
+    /// Requests that had no usable cache entry.
+    pub misses: u64,
+    /// Expired entries renewed by a `304 Not Modified` response.
+    pub revalidations: u64,
+    /// Entries dropped to make room for new ones.
+    pub evictions: u64,
+    /// Entries currently stored.
+    pub entries: usize,
+}
+
+/// Identity of a cached response.
+///
+/// Clients with different tokens share the cache of the client they were
+/// cloned from, so responses are only served to requests made with the same
+/// credentials.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    url: String,
+    credentials: Option<Fingerprint>,
+}
+
+impl Key {
+    fn new(url: &Url, headers: &HeaderMap) -> Self {
+        Self {
+            url: url.to_string(),
+            credentials: Fingerprint::of(headers),
+        }
+    }
+}
+
+/// A cached response.
+#[derive(Debug)]
+struct Entry {
+    path: String,
+    response: TransportResponse,
+    expires: Instant,
+    last_used: u64,
+}
+
+impl Entry {
+    /// Returns true if the entry can be revalidated once expired.
+    fn has_validators(&self) -> bool {
+        self.response.headers.contains_key(ETAG)
+            || self.response.headers.contains_key(LAST_MODIFIED)
+    }
+}
+
+/// Result of looking up a URL in the cache.
+#[derive(Debug)]
+pub(crate) enum Lookup {
+    /// A fresh response that can be used as is.
+    Fresh(TransportResponse),
+    /// An expired response; the headers make the request conditional.
+    Stale(HeaderMap),
+    /// Nothing usable is cached.
+    Miss,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<Key, Entry>,
+    /// Keys of the entries by the tick they were last used at, oldest first.
+    lru: BTreeMap<u64, Key>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl State {
+    /// Advance the LRU clock.
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Mark the entry for `key` as used at `tick`.
+    fn touch(&mut self, key: &Key, tick: u64) -> Option<&mut Entry> {
+        let entry = self.entries.get_mut(key)?;
+        self.lru.remove(&entry.last_used);
+        self.lru.insert(tick, key.clone());
+        entry.last_used = tick;
+        Some(entry)
+    }
+
+    fn insert(&mut self, key: Key, entry: Entry) {
+        self.lru.insert(entry.last_used, key.clone());
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.lru.remove(&old.last_used);
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.last_used);
+        }
+    }
+
+    /// Drop the least recently used entry.
+    fn evict(&mut self) -> bool {
+        let Some((_, key)) = self.lru.pop_first() else {
+            return false;
+        };
+        self.entries.remove(&key);
+        true
+    }
+}
+
+/// Shared LRU cache enforcing a [`CacheConfig`].
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    config: CacheConfig,
+    state: Mutex<State>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache from its configuration.
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::default(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("response cache lock poisoned")
+    }
+
+    /// Look up the response for a request to `url` with `headers`.
+    pub(crate) fn lookup(&self, url: &Url, headers: &HeaderMap) -> Lookup {
+        let key = Key::new(url, headers);
+        let mut state = self.state();
+        let tick = state.tick();
+
+        let now = Instant::now();
+        let lookup = match state.entries.get(&key) {
+            Some(entry) if entry.expires > now => {
+                let entry = state.touch(&key, tick).expect("entry present");
+                Lookup::Fresh(entry.response.clone())
+            }
+            Some(entry) if entry.has_validators() => {
+                let mut headers = HeaderMap::new();
+                if let Some(etag) = entry.response.headers.get(ETAG) {
+                    headers.insert(IF_NONE_MATCH, etag.clone());
+                }
+                if let Some(modified) = entry.response.headers.get(LAST_MODIFIED) {
+                    headers.insert(IF_MODIFIED_SINCE, modified.clone());
+                }
+                Lookup::Stale(headers)
+            }
+            Some(_) => {
+                state.remove(&key);
+                Lookup::Miss
+            }
+            None => Lookup::Miss,
+        };
+
+        match lookup {
+            Lookup::Fresh(_) => state.stats.hits += 1,
+            _ => state.stats.misses += 1,
+        }
+        lookup
+    }
+
+    /// Store a response received for a request to `url` with `headers`,
+    /// whose endpoint path is `path`.
+    ///
+    /// A `304 Not Modified` response renews the stored entry and is replaced
+    /// by it; successful responses are stored. The response to hand to the
+    /// caller is returned, which is the `304` itself if the entry was
+    /// evicted while it was being revalidated.
+    pub(crate) fn store(
+        &self,
+        url: &Url,
+        headers: &HeaderMap,
+        path: &str,
+        response: TransportResponse,
+    ) -> TransportResponse {
+        let key = Key::new(url, headers);
+        let ttl = self.config.ttl_for(path);
+        let mut state = self.state();
+        let tick = state.tick();
+
+        if response.status == StatusCode::NOT_MODIFIED {
+            let Some(entry) = state.touch(&key, tick) else {
+                return response;
+            };
+            for name in [ETAG, LAST_MODIFIED] {
+                if let Some(value) = response.headers.get(&name) {
+                    entry.response.headers.insert(name, value.clone());
+                }
+            }
+            entry.expires = Instant::now() + ttl;
+            let cached = entry.response.clone();
+            state.stats.revalidations += 1;
+            return cached;
+        }
+
+        if response.status != StatusCode::OK
+            || HtmlPage::detect(&response).is_some()
+            || ttl.is_zero()
+            || self.config.max_entries == 0
+            || is_no_store(&response.headers)
+        {
+            return response;
+        }
+
+        if !state.entries.contains_key(&key) {
+            while state.entries.len() >= self.config.max_entries && state.evict() {
+                state.stats.evictions += 1;
+            }
+        }
+        state.insert(
+            key,
+            Entry {
+                path: path.to_string(),
+                response: response.clone(),
+                expires: Instant::now() + ttl,
+                last_used: tick,
+            },
+        );
+        response
+    }
+
+    /// Drop every entry whose endpoint path starts with `path`.
+    pub(crate) fn invalidate(&self, path: &str) {
+        let path = normalize_path(path);
+        let mut state = self.state();
+        let State { entries, lru, .. } = &mut *state;
+        entries.retain(|_, entry| {
+            let keep = !entry.path.starts_with(&path);
+            if !keep {
+                lru.remove(&entry.last_used);
+            }
+            keep
+        });
+    }
+
+    /// Drop every entry.
+    pub(crate) fn clear(&self) {
+        let mut state = self.state();
+        state.entries.clear();
+        state.lru.clear();
+    }
+
+    /// Current counters.
+    pub(crate) fn stats(&self) -> CacheStats {
+        let state = self.state();
+        CacheStats {
+            entries: state.entries.len(),
+            ..state.stats
+        }
+    }
+}
+
+/// Strip the leading slash so paths compare the same way as endpoint paths.
+fn normalize_path(path: &str) -> String {
+    path.trim_start_matches('/').to_string()
+}
+
+/// Returns true if the response must not be cached.
+fn is_no_store(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(reqwest::header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value: &HeaderValue| value.to_str().ok())
+        .any(|value| value.to_ascii_lowercase().contains("no-store"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::AUTHORIZATION;
+
+    fn response(etag: Option<&'static str>) -> TransportResponse {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = etag {
+            headers.insert(ETAG, HeaderValue::from_static(etag));
+        }
+        TransportResponse {
+            status: StatusCode::OK,
+            headers,
+            body: "{}".into(),
+            url: Url::parse("https://mindat.test/v1/").unwrap(),
+        }
+    }
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://mindat.test/v1/")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_ttl_longest_prefix_wins() {
+        let config = CacheConfig::new(10)
+            .ttl(Duration::from_secs(1))
+            .endpoint_ttl("/geomaterials/", Duration::from_secs(2))
+            .endpoint_ttl("geomaterials/search/", Duration::ZERO);
+
+        assert_eq!(config.ttl_for("countries/"), Duration::from_secs(1));
+        assert_eq!(config.ttl_for("geomaterials/3337/"), Duration::from_secs(2));
+        assert_eq!(config.ttl_for("geomaterials/search/"), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_least_recently_used_entry_is_evicted() {
+        let cache = ResponseCache::new(CacheConfig::new(2));
+        for path in ["a/", "b/"] {
+            cache.store(&url(path), &HeaderMap::new(), path, response(None));
+        }
+        assert!(matches!(
+            cache.lookup(&url("a/"), &HeaderMap::new()),
+            Lookup::Fresh(_)
+        ));
+
+        cache.store(&url("c/"), &HeaderMap::new(), "c/", response(None));
+
+        assert!(matches!(
+            cache.lookup(&url("a/"), &HeaderMap::new()),
+            Lookup::Fresh(_)
+        ));
+        assert!(matches!(
+            cache.lookup(&url("b/"), &HeaderMap::new()),
+            Lookup::Miss
+        ));
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_expired_entry_is_revalidated() {
+        let cache = ResponseCache::new(CacheConfig::new(10).ttl(Duration::from_secs(60)));
+        cache.store(
+            &url("a/"),
+            &HeaderMap::new(),
+            "a/",
+            response(Some("\"v1\"")),
+        );
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+
+        let Lookup::Stale(headers) = cache.lookup(&url("a/"), &HeaderMap::new()) else {
+            panic!("expected a stale entry");
+        };
+        assert_eq!(headers[IF_NONE_MATCH], "\"v1\"");
+
+        let mut not_modified = response(None);
+        not_modified.status = StatusCode::NOT_MODIFIED;
+        let renewed = cache.store(&url("a/"), &HeaderMap::new(), "a/", not_modified);
+
+        assert_eq!(renewed.status, StatusCode::OK);
+        assert!(matches!(
+            cache.lookup(&url("a/"), &HeaderMap::new()),
+            Lookup::Fresh(_)
+        ));
+        assert_eq!(cache.stats().revalidations, 1);
+    }
+
+    #[test]
+    fn test_entries_are_kept_apart_per_token() {
+        let cache = ResponseCache::new(CacheConfig::new(10));
+        let mut alice = HeaderMap::new();
+        alice.insert(AUTHORIZATION, HeaderValue::from_static("Token alice"));
+        let mut bob = HeaderMap::new();
+        bob.insert(AUTHORIZATION, HeaderValue::from_static("Token bob"));
+
+        cache.store(&url("a/"), &alice, "a/", response(None));
+
+        assert!(matches!(cache.lookup(&url("a/"), &alice), Lookup::Fresh(_)));
+        assert!(matches!(cache.lookup(&url("a/"), &bob), Lookup::Miss));
+        assert!(matches!(
+            cache.lookup(&url("a/"), &HeaderMap::new()),
+            Lookup::Miss
+        ));
+    }
+}
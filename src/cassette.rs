@@ -0,0 +1,381 @@
+//! Recording and replaying API responses.
+//!
+//! A cassette is a JSON file of request/response pairs. In
+//! [`CassetteMode::Record`] the client makes real requests and keeps every
+//! response, writing them to the file when the cassette is saved or dropped;
+//! in [`CassetteMode::Replay`] it answers requests from the file without
+//! touching the network, which makes tests that use the client fast and
+//! deterministic.
+//!
+//! Requests are matched by method and URL, ignoring the order of query
+//! parameters. A request that was made several times while recording gets its
+//! recorded responses in the same order on replay, with the last one repeated.
+//! A request with no recorded response fails with
+//! [`MindatError::CassetteMiss`].
+//!
+//! Request headers are not recorded, so the API token never reaches the file.
+//! Query parameters that look like credentials are replaced by `REDACTED`, and
+//! `Set-Cookie` response headers are dropped.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example() -> mindat_rs::Result<()> {
+//! use mindat_rs::MindatClient;
+//! use mindat_rs::cassette::CassetteMode;
+//!
+//! // Once, with network access and a real token:
+//! let client = MindatClient::builder()
+//!     .token("your-token")
+//!     .cassette("tests/cassettes/quartz.json", CassetteMode::Record)
+//!     .build()?;
+//! client.geomaterial(3337).await?;
+//! client.save_cassette()?;
+//!
+//! // In CI, without network or token:
+//! let client = MindatClient::builder()
+//!     .cassette("tests/cassettes/quartz.json", CassetteMode::Replay)
+//!     .build()?;
+//! let quartz = client.geomaterial(3337).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, SET_COOKIE};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use crate::error::{MindatError, Result};
+use crate::transport::{Transport, TransportRequest, TransportResponse};
+
+/// Whether a cassette records or replays responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests and save the responses, replacing the file's contents.
+    Record,
+    /// Answer requests from the file only.
+    Replay,
+}
+
+/// A [`Transport`] that records responses to, or replays them from, a file.
+///
+/// Usually set up with
+/// [`MindatClientBuilder::cassette`](crate::MindatClientBuilder::cassette),
+/// which records through the transport the builder would otherwise use.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    inner: Option<Arc<dyn Transport>>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// Number of times each request key has been replayed.
+    replayed: HashMap<String, usize>,
+    /// Whether responses were recorded since the file was last written.
+    unsaved: bool,
+}
+
+impl Cassette {
+    /// Send requests through `inner` and record the responses to `path`.
+    ///
+    /// Responses are kept in memory and written to the file by
+    /// [`save`](Self::save), and when the cassette is dropped.
+    pub fn record(path: impl Into<PathBuf>, inner: impl Transport + 'static) -> Self {
+        Self::recording(path.into(), Arc::new(inner))
+    }
+
+    pub(crate) fn recording(path: PathBuf, inner: Arc<dyn Transport>) -> Self {
+        Self {
+            path,
+            inner: Some(inner),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Replay the responses recorded in `path`.
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let contents = std::fs::read(&path)?;
+        let file: CassetteFile = serde_json::from_slice(&contents).map_err(|e| {
+            MindatError::Config(format!("invalid cassette {}: {}", path.display(), e))
+        })?;
+        Ok(Self {
+            path,
+            inner: None,
+            state: Mutex::new(State {
+                interactions: file.interactions,
+                replayed: HashMap::new(),
+                unsaved: false,
+            }),
+        })
+    }
+
+    /// The cassette file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The mode of this cassette.
+    pub fn mode(&self) -> CassetteMode {
+        match self.inner {
+            Some(_) => CassetteMode::Record,
+            None => CassetteMode::Replay,
+        }
+    }
+
+    /// Find the next recorded response for a request.
+    fn play(&self, request: &TransportRequest) -> Result<TransportResponse> {
+        let method = request.method.as_str();
+        let url = scrub_url(&request.url);
+        let key = format!("{} {}", method, url);
+
+        let mut state = self.state.lock().expect("cassette lock poisoned");
+        let matches: Vec<usize> = state
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.request.method == method && i.request.url == url)
+            .map(|(index, _)| index)
+            .collect();
+        let Some(&last) = matches.last() else {
+            return Err(MindatError::CassetteMiss(format!(
+                "{} (cassette {})",
+                key,
+                self.path.display()
+            )));
+        };
+        let count = state.replayed.entry(key).or_insert(0);
+        let index = matches.get(*count).copied().unwrap_or(last);
+        *count += 1;
+
+        state.interactions[index].response.to_response(&request.url)
+    }
+
+    /// Write the recorded responses to the file.
+    ///
+    /// Does nothing if nothing was recorded since the last save.
+    pub fn save(&self) -> Result<()> {
+        let file = {
+            let mut state = self.state.lock().expect("cassette lock poisoned");
+            if !state.unsaved {
+                return Ok(());
+            }
+            state.unsaved = false;
+            CassetteFile {
+                interactions: state.interactions.clone(),
+            }
+        };
+        let result = write_file(&self.path, &file);
+        if result.is_err() {
+            self.state.lock().expect("cassette lock poisoned").unsaved = true;
+        }
+        result
+    }
+
+    /// Add a response to the cassette.
+    fn add(&self, request: &TransportRequest, response: &TransportResponse) {
+        let interaction = Interaction {
+            request: RecordedRequest {
+                method: request.method.to_string(),
+                url: scrub_url(&request.url),
+            },
+            response: RecordedResponse::new(response),
+        };
+
+        let mut state = self.state.lock().expect("cassette lock poisoned");
+        state.interactions.push(interaction);
+        state.unsaved = true;
+    }
+}
+
+impl Drop for Cassette {
+    fn drop(&mut self) {
+        if self.state.is_poisoned() {
+            return;
+        }
+        // Errors cannot be reported here; call `save` to see them
+        let _ = self.save();
+    }
+}
+
+/// Write a cassette file, creating its directory if needed.
+fn write_file(path: &Path, file: &CassetteFile) -> Result<()> {
+    let json = serde_json::to_vec_pretty(file).map_err(std::io::Error::other)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+impl Transport for Cassette {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>> {
+        Box::pin(async move {
+            let Some(inner) = &self.inner else {
+                return self.play(&request);
+            };
+            let response = inner.send(request.clone()).await?;
+            self.add(&request, &response);
+            Ok(response)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    /// Every value of each header, in the order received.
+    #[serde(default)]
+    headers: BTreeMap<String, Vec<String>>,
+    body: Body,
+}
+
+/// A response body, kept readable when it is text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Body {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl RecordedResponse {
+    fn new(response: &TransportResponse) -> Self {
+        let mut headers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, value) in &response.headers {
+            if *name == SET_COOKIE {
+                continue;
+            }
+            if let Ok(value) = value.to_str() {
+                headers
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(value.to_string());
+            }
+        }
+        let body = match std::str::from_utf8(&response.body) {
+            Ok(text) => Body::Text(text.to_string()),
+            Err(_) => Body::Bytes(response.body.to_vec()),
+        };
+        Self {
+            status: response.status.as_u16(),
+            headers,
+            body,
+        }
+    }
+
+    fn to_response(&self, url: &Url) -> Result<TransportResponse> {
+        let invalid = |what: &str| MindatError::Config(format!("invalid {} in cassette", what));
+
+        let status = StatusCode::from_u16(self.status).map_err(|_| invalid("status"))?;
+        let mut headers = HeaderMap::new();
+        for (name, values) in &self.headers {
+            let name = HeaderName::try_from(name.as_str()).map_err(|_| invalid("header name"))?;
+            for value in values {
+                headers.append(
+                    &name,
+                    HeaderValue::from_str(value).map_err(|_| invalid("header value"))?,
+                );
+            }
+        }
+        let body = match &self.body {
+            Body::Text(text) => Bytes::from(text.clone()),
+            Body::Bytes(bytes) => Bytes::from(bytes.clone()),
+        };
+        Ok(TransportResponse {
+            status,
+            headers,
+            body,
+            url: url.clone(),
+        })
+    }
+}
+
+/// The URL with its query sorted and credential-like parameters redacted.
+fn scrub_url(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .into_owned()
+        .map(|(key, value)| {
+            let lower = key.to_ascii_lowercase();
+            if lower.contains("token") || lower.contains("key") {
+                (key, "REDACTED".to_string())
+            } else {
+                (key, value)
+            }
+        })
+        .collect();
+    pairs.sort();
+
+    let mut scrubbed = url.clone();
+    scrubbed.set_fragment(None);
+    if pairs.is_empty() {
+        scrubbed.set_query(None);
+    } else {
+        scrubbed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    scrubbed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub_url_sorts_and_redacts() {
+        let url =
+            Url::parse("https://api.mindat.org/v1/geomaterials/?name=quartz&api_key=abc&ima=1")
+                .unwrap();
+        assert_eq!(
+            scrub_url(&url),
+            "https://api.mindat.org/v1/geomaterials/?api_key=REDACTED&ima=1&name=quartz"
+        );
+    }
+
+    #[test]
+    fn test_recorded_response_keeps_repeated_headers() {
+        let url = Url::parse("https://api.mindat.org/v1/geomaterials/").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.append("warning", HeaderValue::from_static("199 - \"first\""));
+        headers.append("warning", HeaderValue::from_static("199 - \"second\""));
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=abc"));
+        let response = TransportResponse {
+            status: StatusCode::OK,
+            headers,
+            body: Bytes::from_static(b"{}"),
+            url: url.clone(),
+        };
+
+        let json = serde_json::to_string(&RecordedResponse::new(&response)).unwrap();
+        let recorded: RecordedResponse = serde_json::from_str(&json).unwrap();
+        let replayed = recorded.to_response(&url).unwrap();
+        let warnings: Vec<_> = replayed.headers.get_all("warning").iter().collect();
+        assert_eq!(warnings, ["199 - \"first\"", "199 - \"second\""]);
+        assert!(replayed.headers.get(SET_COOKIE).is_none());
+    }
+}
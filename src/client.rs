@@ -1,37 +1,73 @@
 //! HTTP client for the Mindat API.
 
-use reqwest::Client;
-use reqwest::header::{ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue, USER_AGENT};
-use std::time::Duration;
+use reqwest::header::{
+    ACCEPT, AUTHORIZATION, HeaderMap, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, USER_AGENT,
+};
+use reqwest::{Client, Method, Proxy, StatusCode};
+#[cfg(feature = "cassette")]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use url::Url;
-
-use crate::error::{MindatError, Result};
+use zeroize::Zeroizing;
+
+use crate::batch::{self, BatchResponse};
+use crate::cache::{CacheConfig, CacheStats, Lookup, ResponseCache};
+#[cfg(feature = "cassette")]
+use crate::cassette::{Cassette, CassetteMode};
+use crate::coalesce::{self, Coalescer, Exchange, Flight};
+use crate::credentials::{
+    ConfigFile, CredentialProvider, DEFAULT_PROFILE, Profile, SecretToken, StaticToken,
+};
+use crate::decode::{self, DecodeMode, SkippedRecord};
+#[cfg(feature = "disk-cache")]
+use crate::disk_cache::{DiskCache, DiskCacheConfig};
+use crate::error::{self, HtmlPage, MindatError, ResponseDetails, Result};
+use crate::middleware::{self, Middleware};
 use crate::models::*;
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::response::Response;
+use crate::retry::RetryPolicy;
+use crate::stream::{ItemStream, PageStream, Paginated};
+use crate::telemetry::Telemetry;
+use crate::transport::{
+    ReqwestTransport, StreamingResponse, Transport, TransportRequest, TransportResponse,
+};
 
 /// Default base URL for the Mindat API (v1).
 /// Note: Must end with a slash for proper URL joining.
 pub const DEFAULT_BASE_URL: &str = "https://api.mindat.org/v1/";
 
-/// User-Agent string for API requests.
+/// Default User-Agent string for API requests.
 /// Using a browser-like User-Agent to avoid Cloudflare blocks.
-const USER_AGENT_STRING: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
-
-/// Create a configured HTTP client with proper timeouts and settings.
-fn create_http_client() -> Client {
-    Client::builder()
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
-        .pool_max_idle_per_host(5)
-        .build()
-        .expect("Failed to create HTTP client")
-}
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// Default total request timeout.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default connection timeout.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default number of idle connections kept per host.
+const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 5;
 
 /// Client for interacting with the Mindat API.
 #[derive(Debug, Clone)]
 pub struct MindatClient {
-    http: Client,
+    transport: Arc<dyn Transport>,
+    middleware: Vec<Arc<dyn Middleware>>,
     base_url: Url,
-    token: Option<String>,
+    default_headers: HeaderMap,
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    retry: RetryPolicy,
+    limiter: Option<Arc<RateLimiter>>,
+    cache: Option<Arc<ResponseCache>>,
+    #[cfg(feature = "disk-cache")]
+    disk_cache: Option<Arc<DiskCache>>,
+    coalescer: Option<Arc<Coalescer>>,
+    decode_mode: DecodeMode,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Arc<Cassette>>,
 }
 
 impl MindatClient {
@@ -44,22 +80,41 @@ impl MindatClient {
     ///
     /// let client = MindatClient::new("your-api-token");
     /// ```
-    pub fn new(token: impl Into<String>) -> Self {
-        Self {
-            http: create_http_client(),
-            base_url: Url::parse(DEFAULT_BASE_URL).unwrap(),
-            token: Some(token.into()),
-        }
+    pub fn new(token: impl Into<SecretToken>) -> Self {
+        Self::builder()
+            .token(token)
+            .build()
+            .expect("Failed to create HTTP client")
     }
 
     /// Create a new client without authentication.
     /// Some endpoints (like minerals_ima) work without authentication.
     pub fn anonymous() -> Self {
-        Self {
-            http: create_http_client(),
-            base_url: Url::parse(DEFAULT_BASE_URL).unwrap(),
-            token: None,
-        }
+        Self::builder()
+            .build()
+            .expect("Failed to create HTTP client")
+    }
+
+    /// Create a client from a profile in the configuration file, or from
+    /// the [`default`](crate::credentials::DEFAULT_PROFILE) profile when
+    /// `name` is `None`.
+    ///
+    /// See [`credentials`](crate::credentials) for the file's location and
+    /// format.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mindat_rs::MindatClient;
+    ///
+    /// let client = MindatClient::from_profile(Some("work"))?;
+    /// let default = MindatClient::from_profile(None)?;
+    /// # Ok::<(), mindat_rs::MindatError>(())
+    /// ```
+    pub fn from_profile(name: Option<&str>) -> Result<Self> {
+        let config = ConfigFile::load()?;
+        let name = name.unwrap_or(DEFAULT_PROFILE);
+        Self::builder().profile(config.profile(name)?).build()
     }
 
     /// Create a new client builder for more configuration options.
@@ -68,8 +123,13 @@ impl MindatClient {
     }
 
     /// Set the API token.
-    pub fn set_token(&mut self, token: impl Into<String>) {
-        self.token = Some(token.into());
+    pub fn set_token(&mut self, token: impl Into<SecretToken>) {
+        self.credentials = Some(Arc::new(StaticToken::new(token)));
+    }
+
+    /// Get the token for requests from a [`CredentialProvider`].
+    pub fn set_credentials(&mut self, provider: impl CredentialProvider + 'static) {
+        self.credentials = Some(Arc::new(provider));
     }
 
     /// Get the base URL.
@@ -77,80 +137,499 @@ impl MindatClient {
         &self.base_url
     }
 
+    /// How response bodies are decoded.
+    pub(crate) fn decode_mode(&self) -> DecodeMode {
+        self.decode_mode
+    }
+
+    /// Get response cache counters, if a cache is configured.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Drop cached responses of endpoints under `path`, such as
+    /// `"/geomaterials/3337/"` or `"/locality-type/"`.
+    pub fn invalidate_cache(&self, path: &str) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(path);
+        }
+    }
+
+    /// Drop every cached response.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Write the responses recorded so far to the cassette file.
+    ///
+    /// A recording cassette is also saved when the last clone of the client
+    /// is dropped, but errors are only reported here. Does nothing when not
+    /// recording.
+    #[cfg(feature = "cassette")]
+    pub fn save_cassette(&self) -> Result<()> {
+        match &self.cassette {
+            Some(cassette) => cassette.save(),
+            None => Ok(()),
+        }
+    }
+
     /// Build request headers.
     fn headers(&self) -> Result<HeaderMap> {
-        let mut headers = HeaderMap::new();
+        // Always includes User-Agent and Accept to avoid Cloudflare blocks
+        let mut headers = self.default_headers.clone();
 
-        // Always include User-Agent and Accept to avoid Cloudflare blocks
-        headers.insert(USER_AGENT, HeaderValue::from_static(USER_AGENT_STRING));
-        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-
-        if let Some(ref token) = self.token {
-            let auth_value = format!("Token {}", token);
-            headers.insert(
-                AUTHORIZATION,
-                HeaderValue::from_str(&auth_value).map_err(|_| {
-                    MindatError::InvalidParameter("Invalid token format".to_string())
-                })?,
-            );
+        let token = match &self.credentials {
+            Some(credentials) => credentials.token()?,
+            None => None,
+        };
+        if let Some(token) = token {
+            let auth_value = Zeroizing::new(format!("Token {}", token.expose()));
+            let mut value = HeaderValue::from_str(&auth_value)
+                .map_err(|_| MindatError::InvalidParameter("Invalid token format".to_string()))?;
+            // Keeps the value out of `Debug` output of requests and headers
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
         }
         Ok(headers)
     }
 
-    /// Make a GET request to the API.
-    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+    /// Path of a URL relative to the base URL, e.g. `geomaterials/3337/`.
+    fn endpoint_path<'a>(&self, url: &'a Url) -> &'a str {
+        url.path()
+            .strip_prefix(self.base_url.path())
+            .unwrap_or(url.path())
+    }
+
+    /// Resolve an endpoint path against the base URL.
+    fn endpoint_url(&self, path: &str) -> Result<Url> {
         // Strip leading slash to ensure proper URL joining with base URL
         let path = path.strip_prefix('/').unwrap_or(path);
-        let url = self.base_url.join(path)?;
-        let response = self.http.get(url).headers(self.headers()?).send().await?;
+        Ok(self.base_url.join(path)?)
+    }
+
+    /// Resolve an endpoint path and encode query parameters into it.
+    pub(crate) fn endpoint_url_with_query<Q: serde::Serialize + ?Sized>(
+        &self,
+        path: &str,
+        query: &Q,
+    ) -> Result<Url> {
+        let mut url = self.endpoint_url(path)?;
+        let encoded = serde_urlencoded::to_string(query)
+            .map_err(|e| MindatError::InvalidParameter(e.to_string()))?;
+        if !encoded.is_empty() {
+            url.set_query(Some(&encoded));
+        }
+        Ok(url)
+    }
 
-        self.handle_response(response).await
+    /// Make a GET request to the API.
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.get_url(self.endpoint_url(path)?).await
     }
 
     /// Make a GET request with query parameters.
     async fn get_with_query<T, Q>(&self, path: &str, query: &Q) -> Result<T>
     where
         T: serde::de::DeserializeOwned,
-        Q: serde::Serialize,
+        Q: serde::Serialize + ?Sized,
     {
-        // Strip leading slash to ensure proper URL joining with base URL
-        let path = path.strip_prefix('/').unwrap_or(path);
-        let url = self.base_url.join(path)?;
-        let response = self
-            .http
-            .get(url)
-            .headers(self.headers()?)
-            .query(query)
-            .send()
-            .await?;
+        self.get_url(self.endpoint_url_with_query(path, query)?)
+            .await
+    }
+
+    /// Make a GET request, keeping the response's metadata.
+    async fn get_with_meta<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> Result<Response<T>> {
+        self.get_url_with_meta(self.endpoint_url(path)?).await
+    }
+
+    /// Make a GET request with query parameters, keeping the response's
+    /// metadata.
+    async fn get_with_query_meta<T, Q>(&self, path: &str, query: &Q) -> Result<Response<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        Q: serde::Serialize + ?Sized,
+    {
+        self.get_url_with_meta(self.endpoint_url_with_query(path, query)?)
+            .await
+    }
+
+    /// Make a GET request for a page of results with query parameters.
+    async fn get_page<P, T, Q>(&self, path: &str, query: &Q) -> Result<P>
+    where
+        P: serde::de::DeserializeOwned + Paginated<T>,
+        Q: serde::Serialize + ?Sized,
+    {
+        self.get_page_meta(path, query)
+            .await
+            .map(Response::into_body)
+    }
+
+    /// Make a GET request for a page of results with query parameters,
+    /// keeping the response's metadata.
+    async fn get_page_meta<P, T, Q>(&self, path: &str, query: &Q) -> Result<Response<P>>
+    where
+        P: serde::de::DeserializeOwned + Paginated<T>,
+        Q: serde::Serialize + ?Sized,
+    {
+        let url = self.endpoint_url_with_query(path, query)?;
+        self.get_url_counted(url, |page: &P| Some(page.result_count()))
+            .await
+    }
+
+    /// Make a GET request for a page of results at a fully resolved URL.
+    pub(crate) async fn get_page_url<P, T>(&self, url: Url) -> Result<P>
+    where
+        P: serde::de::DeserializeOwned + Paginated<T>,
+    {
+        self.get_url_counted(url, |page: &P| Some(page.result_count()))
+            .await
+            .map(Response::into_body)
+    }
+
+    /// Make a GET request to a fully resolved URL.
+    ///
+    /// Failed attempts are retried according to the client's retry policy.
+    /// Responses are served from and stored in the cache, if configured.
+    async fn get_url<T: serde::de::DeserializeOwned>(&self, url: Url) -> Result<T> {
+        self.get_url_with_meta(url).await.map(Response::into_body)
+    }
+
+    /// Make a GET request to a fully resolved URL, keeping the response's
+    /// metadata.
+    async fn get_url_with_meta<T: serde::de::DeserializeOwned>(
+        &self,
+        url: Url,
+    ) -> Result<Response<T>> {
+        self.get_url_counted(url, |_| None).await
+    }
+
+    /// Make a GET request to a fully resolved URL, reporting the number of
+    /// results `count` finds in the decoded body.
+    async fn get_url_counted<T: serde::de::DeserializeOwned>(
+        &self,
+        url: Url,
+        count: fn(&T) -> Option<usize>,
+    ) -> Result<Response<T>> {
+        let mut telemetry = Telemetry::start(self.endpoint_path(&url), &url);
+
+        #[cfg(feature = "tracing")]
+        let result = {
+            use tracing::Instrument;
+            let span = telemetry.span();
+            self.fetch_url(url, &mut telemetry).instrument(span).await
+        };
+        #[cfg(not(feature = "tracing"))]
+        let result = self.fetch_url(url, &mut telemetry).await;
+
+        if let Some(results) = result.as_ref().ok().and_then(|r| count(&r.body)) {
+            telemetry.results(results);
+        }
+        telemetry.finish(&result);
+        result
+    }
+
+    /// Fetch and decode a URL, recording what happened in `telemetry`.
+    async fn fetch_url<T: serde::de::DeserializeOwned>(
+        &self,
+        url: Url,
+        telemetry: &mut Telemetry,
+    ) -> Result<Response<T>> {
+        let start = Instant::now();
+        let mut headers = self.headers()?;
+        match self
+            .cache
+            .as_ref()
+            .map(|cache| cache.lookup(&url, &headers))
+        {
+            Some(Lookup::Fresh(response)) => {
+                telemetry.cached(&response);
+                return self.decode_response(response, start, 0);
+            }
+            Some(Lookup::Stale(conditional)) => headers.extend(conditional),
+            Some(Lookup::Miss) | None => {}
+        }
+        #[cfg(feature = "disk-cache")]
+        if let Some(disk) = &self.disk_cache {
+            if let Some(response) = disk.load(&url, &headers).await? {
+                telemetry.cached(&response);
+                return self.decode_response(response, start, 0);
+            }
+        }
+
+        let exchange = match &self.coalescer {
+            Some(coalescer) => match coalescer.join(&url, &headers) {
+                Flight::Leader(leader) => leader.finish(self.send(&url, headers, telemetry).await),
+                Flight::Follower(receiver) => match coalesce::wait(receiver).await {
+                    Some(exchange) => exchange,
+                    None => self.send(&url, headers, telemetry).await,
+                },
+            },
+            None => self.send(&url, headers, telemetry).await,
+        };
+        let (response, attempts) = exchange?;
+        telemetry.response(&response, attempts);
+        self.decode_response(response, start, attempts)
+            .map_err(|e| retries_exhausted(e, attempts))
+    }
+
+    /// Handle a response and wrap the body with the response's metadata.
+    ///
+    /// `attempts` is 0 for responses served from a cache.
+    fn decode_response<T: serde::de::DeserializeOwned>(
+        &self,
+        response: TransportResponse,
+        start: Instant,
+        attempts: u32,
+    ) -> Result<Response<T>> {
+        let status = response.status;
+        let headers = response.headers.clone();
+        let url = response.url.clone();
+        let (body, skipped) = self.handle_response(response)?;
+        Ok(Response {
+            body,
+            status,
+            headers,
+            url,
+            elapsed: start.elapsed(),
+            attempts,
+            cached: attempts == 0,
+            skipped,
+        })
+    }
+
+    /// Send a request, retrying failed attempts according to the retry
+    /// policy, and store the final response in the caches.
+    async fn send(
+        &self,
+        url: &Url,
+        mut headers: HeaderMap,
+        telemetry: &mut Telemetry,
+    ) -> Result<Exchange> {
+        let mut attempt = 1;
+
+        loop {
+            // Held until the response has been received
+            let permit = match &self.limiter {
+                Some(limiter) => Some(limiter.acquire().await),
+                None => None,
+            };
+
+            let request = TransportRequest {
+                method: Method::GET,
+                url: url.clone(),
+                headers: headers.clone(),
+            };
+            let result = middleware::send(&*self.transport, &self.middleware, request).await;
+            drop(permit);
+
+            let delay = match &result {
+                Ok(response) => {
+                    self.retry
+                        .retry_status_delay(response.status, &response.headers, attempt)
+                }
+                Err(e) => self.retry.retry_error_delay(e, attempt),
+            };
+            if let Some(delay) = delay {
+                telemetry.retry(attempt, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+
+            let response = match result {
+                Ok(response) => self.cache_response(url, &headers, response).await,
+                Err(e) => {
+                    telemetry.failed(attempt);
+                    return Err(retries_exhausted(e, attempt));
+                }
+            };
+            // The entry being revalidated was evicted in the meantime, so
+            // ask for the whole response again
+            if response.status == StatusCode::NOT_MODIFIED && remove_conditions(&mut headers) {
+                attempt += 1;
+                continue;
+            }
+            return Ok((response, attempt));
+        }
+    }
+
+    /// Request a fully resolved URL without reading the response body.
+    ///
+    /// Used for streamed pages, so the in-memory cache and request coalescing
+    /// are bypassed. The disk cache needs whole bodies, so when one is
+    /// configured pages are read in full and served from or stored in it
+    /// like any other response. Failed attempts are retried according to the
+    /// retry policy, and error responses are read in full and mapped like any
+    /// other.
+    pub(crate) async fn open_url(&self, url: Url) -> Result<StreamingResponse> {
+        let mut telemetry = Telemetry::start(self.endpoint_path(&url), &url);
+
+        #[cfg(feature = "tracing")]
+        let result = {
+            use tracing::Instrument;
+            let span = telemetry.span();
+            self.send_streaming(url, &mut telemetry)
+                .instrument(span)
+                .await
+        };
+        #[cfg(not(feature = "tracing"))]
+        let result = self.send_streaming(url, &mut telemetry).await;
+
+        telemetry.finish(&result);
+        result
+    }
+
+    /// Send a streamed request, retrying failed attempts according to the
+    /// retry policy.
+    async fn send_streaming(
+        &self,
+        url: Url,
+        telemetry: &mut Telemetry,
+    ) -> Result<StreamingResponse> {
+        let headers = self.headers()?;
+
+        #[cfg(feature = "disk-cache")]
+        if let Some(disk) = &self.disk_cache {
+            if let Some(response) = disk.load(&url, &headers).await? {
+                telemetry.cached(&response);
+                return Ok(response.into());
+            }
+            let (response, attempts) = self.send(&url, headers, telemetry).await?;
+            telemetry.response(&response, attempts);
+            return self
+                .whole_response(response)
+                .map_err(|e| retries_exhausted(e, attempts));
+        }
+
+        let mut attempt = 1;
+
+        loop {
+            // Held until the response headers have been received, not while
+            // the body is streamed to the consumer
+            let permit = match &self.limiter {
+                Some(limiter) => Some(limiter.acquire().await),
+                None => None,
+            };
+
+            let request = TransportRequest {
+                method: Method::GET,
+                url: url.clone(),
+                headers: headers.clone(),
+            };
+            let result =
+                middleware::send_streaming(&*self.transport, &self.middleware, request).await;
+            drop(permit);
+
+            let delay = match &result {
+                Ok(response) => {
+                    self.retry
+                        .retry_status_delay(response.status, &response.headers, attempt)
+                }
+                Err(e) => self.retry.retry_error_delay(e, attempt),
+            };
+            if let Some(delay) = delay {
+                telemetry.retry(attempt, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
 
-        self.handle_response(response).await
+            let response = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    telemetry.failed(attempt);
+                    return Err(retries_exhausted(e, attempt));
+                }
+            };
+            telemetry.streamed(response.status, attempt);
+            if response.is_streamable() {
+                return Ok(response);
+            }
+
+            // Error responses and HTML pages have already been read whole
+            let response = response.collect().await?;
+            return self
+                .whole_response(response)
+                .map_err(|e| retries_exhausted(e, attempt));
+        }
+    }
+
+    /// Hand a fully read response to a streamed request, mapping error
+    /// responses and HTML pages to errors.
+    fn whole_response(&self, response: TransportResponse) -> Result<StreamingResponse> {
+        if response.status.is_success() && HtmlPage::detect(&response).is_none() {
+            return Ok(response.into());
+        }
+        self.handle_response::<serde::de::IgnoredAny>(response.clone())?;
+        Ok(response.into())
+    }
+
+    /// Store a response in the configured caches, returning the response to
+    /// hand to the caller.
+    async fn cache_response(
+        &self,
+        url: &Url,
+        headers: &HeaderMap,
+        response: TransportResponse,
+    ) -> TransportResponse {
+        let response = match &self.cache {
+            Some(cache) => cache.store(url, headers, self.endpoint_path(url), response),
+            None => response,
+        };
+        #[cfg(feature = "disk-cache")]
+        if let Some(disk) = &self.disk_cache {
+            disk.store(url, headers, &response).await;
+        }
+        response
     }
 
-    /// Handle API response.
-    async fn handle_response<T: serde::de::DeserializeOwned>(
+    /// Handle API response, returning the body with the results skipped in
+    /// lenient mode.
+    fn handle_response<T: serde::de::DeserializeOwned>(
         &self,
-        response: reqwest::Response,
-    ) -> Result<T> {
-        let status = response.status();
+        response: TransportResponse,
+    ) -> Result<(T, Vec<SkippedRecord>)> {
+        let status = response.status;
+
+        // Statuses with their own error variant keep it when the body is just
+        // an HTML rendering of the error, but Cloudflare pages always win.
+        if let Some(page) = HtmlPage::detect(&response) {
+            let has_variant = matches!(status.as_u16(), 401 | 404 | 429);
+            if !has_variant || !matches!(page, HtmlPage::Other { .. }) {
+                return Err(MindatError::Html {
+                    page,
+                    details: Box::new(ResponseDetails::new(&response)),
+                });
+            }
+        }
 
         if status.is_success() {
-            let text = response.text().await?;
-            serde_json::from_str(&text).map_err(MindatError::from)
+            decode(&response, self.decode_mode)
         } else {
-            let status_code = status.as_u16();
-            let message = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-
-            match status_code {
-                401 => Err(MindatError::AuthenticationRequired),
-                404 => Err(MindatError::NotFound(message)),
-                429 => Err(MindatError::RateLimited),
-                _ => Err(MindatError::Api {
-                    status: status_code,
-                    message,
+            match status.as_u16() {
+                401 => Err(MindatError::AuthenticationRequired {
+                    details: Box::new(ResponseDetails::new(&response)),
+                }),
+                404 => Err(MindatError::NotFound {
+                    message: error::snippet(&response.body),
+                    details: Box::new(ResponseDetails::new(&response)),
+                }),
+                429 => {
+                    let details = ResponseDetails::new(&response);
+                    Err(MindatError::RateLimited {
+                        retry_after: details.retry_after,
+                        details: Box::new(details),
+                    })
+                }
+                status => Err(MindatError::Api {
+                    status,
+                    message: error::snippet(&response.body),
+                    details: Box::new(ResponseDetails::new(&response)),
                 }),
             }
         }
@@ -177,7 +656,7 @@ impl MindatClient {
     pub async fn countries(&self) -> Result<PaginatedResponse<Country>> {
         // Note: The /countries/ endpoint may not exist in v1 API
         // Countries are primarily available as filters on the localities endpoint
-        self.get("/countries/").await
+        self.get_page("/countries/", &()).await
     }
 
     /// List countries with pagination.
@@ -187,7 +666,7 @@ impl MindatClient {
             page: i32,
         }
         // Note: The /countries/ endpoint may not exist in v1 API
-        self.get_with_query("/countries/", &Query { page }).await
+        self.get_page("/countries/", &Query { page }).await
     }
 
     /// Get a specific country by ID.
@@ -224,104 +703,75 @@ impl MindatClient {
         &self,
         query: GeomaterialsQuery,
     ) -> Result<PaginatedResponse<Geomaterial>> {
-        #[derive(serde::Serialize)]
-        struct QueryParams {
-            #[serde(skip_serializing_if = "Option::is_none")]
-            name: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            q: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            ima: Option<bool>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            elements_inc: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            elements_exc: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            colour: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            streak: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            hardness_min: Option<f32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            hardness_max: Option<f32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            density_min: Option<f64>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            density_max: Option<f64>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            ri_min: Option<f32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            ri_max: Option<f32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            bi_min: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            bi_max: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            optical2v_min: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            optical2v_max: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            varietyof: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            synid: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            polytypeof: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            groupid: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            non_utf: Option<bool>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            meteoritical_code: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            meteoritical_code_exists: Option<bool>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            updated_at: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            fields: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            omit: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            ordering: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            page: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            page_size: Option<i32>,
-        }
-
-        let params = QueryParams {
-            name: query.name,
-            q: query.q,
-            ima: query.ima,
-            elements_inc: query.elements_inc,
-            elements_exc: query.elements_exc,
-            colour: query.colour,
-            streak: query.streak,
-            hardness_min: query.hardness_min,
-            hardness_max: query.hardness_max,
-            density_min: query.density_min,
-            density_max: query.density_max,
-            ri_min: query.ri_min,
-            ri_max: query.ri_max,
-            bi_min: query.bi_min,
-            bi_max: query.bi_max,
-            optical2v_min: query.optical2v_min,
-            optical2v_max: query.optical2v_max,
-            varietyof: query.varietyof,
-            synid: query.synid,
-            polytypeof: query.polytypeof,
-            groupid: query.groupid,
-            non_utf: query.non_utf,
-            meteoritical_code: query.meteoritical_code,
-            meteoritical_code_exists: query.meteoritical_code_exists,
-            updated_at: query.updated_at,
-            fields: query.fields,
-            omit: query.omit,
-            ordering: query.ordering.map(|o| o.to_string()),
-            page: query.page,
-            page_size: query.page_size,
-        };
+        self.get_page("/geomaterials/", &QueryPairs::from(query))
+            .await
+    }
 
-        self.get_with_query("/geomaterials/", &params).await
+    /// List geomaterials, with the response's metadata.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> mindat_rs::Result<()> {
+    /// use mindat_rs::{MindatClient, GeomaterialsQuery};
+    ///
+    /// let client = MindatClient::new("your-token");
+    /// let response = client
+    ///     .geomaterials_with_meta(GeomaterialsQuery::new().name("quartz"))
+    ///     .await?;
+    /// println!(
+    ///     "{} results in {:?}, {:?} requests left",
+    ///     response.body.results.len(),
+    ///     response.elapsed,
+    ///     response.rate_limit_remaining(),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn geomaterials_with_meta(
+        &self,
+        query: GeomaterialsQuery,
+    ) -> Result<Response<PaginatedResponse<Geomaterial>>> {
+        self.get_page_meta("/geomaterials/", &QueryPairs::from(query))
+            .await
+    }
+
+    /// Stream geomaterials matching the query, following pagination.
+    ///
+    /// Results are yielded one at a time and further pages are requested as
+    /// needed. See [`PageStream`] for limits and prefetching.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> mindat_rs::Result<()> {
+    /// use futures::TryStreamExt;
+    /// use mindat_rs::{MindatClient, GeomaterialsQuery};
+    ///
+    /// let client = MindatClient::new("your-token");
+    /// let query = GeomaterialsQuery::new().ima_approved(true).page_size(100);
+    ///
+    /// let minerals: Vec<_> = client
+    ///     .geomaterials_stream(query)
+    ///     .max_items(1000)
+    ///     .try_collect()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn geomaterials_stream(&self, query: GeomaterialsQuery) -> PageStream<Geomaterial> {
+        let start = self.endpoint_url_with_query("/geomaterials/", &QueryPairs::from(query));
+        PageStream::new::<PaginatedResponse<Geomaterial>>(self.clone(), start)
+    }
+
+    /// Stream geomaterials matching the query, decoding each result as soon
+    /// as it is received.
+    ///
+    /// Suited to large pages: unlike [`geomaterials_stream`](Self::geomaterials_stream)
+    /// a page is never held in memory as a whole. See [`ItemStream`].
+    pub fn geomaterials_items(&self, query: GeomaterialsQuery) -> ItemStream<Geomaterial> {
+        let start = self.endpoint_url_with_query("/geomaterials/", &QueryPairs::from(query));
+        ItemStream::new(self.clone(), start)
     }
 
     /// Get a specific geomaterial by ID.
@@ -329,6 +779,43 @@ impl MindatClient {
         self.get(&format!("/geomaterials/{}/", id)).await
     }
 
+    /// Get a specific geomaterial by ID, with the response's metadata.
+    pub async fn geomaterial_with_meta(&self, id: i32) -> Result<Response<Geomaterial>> {
+        self.get_with_meta(&format!("/geomaterials/{}/", id)).await
+    }
+
+    /// Get many geomaterials by ID.
+    ///
+    /// The IDs are fetched in chunks with bounded concurrency; see
+    /// [`batch`]. Results keep the order of `ids`, and IDs the
+    /// API returned nothing for are listed in [`BatchResponse::missing`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> mindat_rs::Result<()> {
+    /// use mindat_rs::MindatClient;
+    ///
+    /// let client = MindatClient::new("your-token");
+    /// let batch = client.geomaterials_by_ids(&[3337, 1576, 727]).await?;
+    /// for mineral in &batch.results {
+    ///     println!("{}: {:?}", mineral.id, mineral.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn geomaterials_by_ids(&self, ids: &[i32]) -> Result<BatchResponse<Geomaterial>> {
+        batch::fetch_by_ids(
+            ids,
+            |chunk| {
+                let page_size = chunk.len() as i32;
+                self.geomaterials_stream(GeomaterialsQuery::new().ids(chunk).page_size(page_size))
+            },
+            |mineral| mineral.id,
+        )
+        .await
+    }
+
     /// Get varieties of a specific geomaterial.
     pub async fn geomaterial_varieties(&self, id: i32) -> Result<Geomaterial> {
         self.get(&format!("/geomaterials/{}/varieties/", id)).await
@@ -375,47 +862,74 @@ impl MindatClient {
         &self,
         query: LocalitiesQuery,
     ) -> Result<CursorPaginatedResponse<Locality>> {
-        #[derive(serde::Serialize)]
-        struct QueryParams {
-            #[serde(skip_serializing_if = "Option::is_none")]
-            country: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            txt: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            description: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            elements_inc: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            elements_exc: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            updated_at: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            fields: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            omit: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            cursor: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            page_size: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            page: Option<i32>,
-        }
+        self.get_page("/localities/", &QueryPairs::from(query))
+            .await
+    }
 
-        let params = QueryParams {
-            country: query.country,
-            txt: query.txt,
-            description: query.description,
-            elements_inc: query.elements_inc,
-            elements_exc: query.elements_exc,
-            updated_at: query.updated_at,
-            fields: query.fields,
-            omit: query.omit,
-            cursor: query.cursor,
-            page_size: query.page_size,
-            page: query.page,
-        };
+    /// List localities, with the response's metadata.
+    pub async fn localities_with_meta(
+        &self,
+        query: LocalitiesQuery,
+    ) -> Result<Response<CursorPaginatedResponse<Locality>>> {
+        self.get_page_meta("/localities/", &QueryPairs::from(query))
+            .await
+    }
 
-        self.get_with_query("/localities/", &params).await
+    /// Stream localities matching the query, following cursor pagination.
+    ///
+    /// The stream follows the cursor links returned by the API, falling back
+    /// to page numbers if the server returns page links instead. Use
+    /// [`PageStream::resume_cursor`] to continue an interrupted iteration.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> mindat_rs::Result<()> {
+    /// use futures::StreamExt;
+    /// use mindat_rs::{MindatClient, LocalitiesQuery};
+    ///
+    /// let client = MindatClient::new("your-token");
+    /// let mut localities = client.localities_stream(LocalitiesQuery::new().country("Brazil"));
+    ///
+    /// while let Some(loc) = localities.next().await {
+    ///     match loc {
+    ///         Ok(loc) => println!("{}: {:?}", loc.id, loc.txt),
+    ///         Err(e) => {
+    ///             // Save the position and pick up from here later
+    ///             let cursor = localities.resume_cursor();
+    ///             eprintln!("interrupted at {:?}: {}", cursor, e);
+    ///             break;
+    ///         }
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn localities_stream(&self, query: LocalitiesQuery) -> PageStream<Locality> {
+        let start = self.endpoint_url_with_query("/localities/", &QueryPairs::from(query));
+        PageStream::new::<CursorPaginatedResponse<Locality>>(self.clone(), start)
+    }
+
+    /// Stream localities matching the query, decoding each result as soon as
+    /// it is received. See [`ItemStream`].
+    pub fn localities_items(&self, query: LocalitiesQuery) -> ItemStream<Locality> {
+        let start = self.endpoint_url_with_query("/localities/", &QueryPairs::from(query));
+        ItemStream::new(self.clone(), start)
+    }
+
+    /// Get many localities by ID.
+    ///
+    /// Works like [`geomaterials_by_ids`](Self::geomaterials_by_ids).
+    pub async fn localities_by_ids(&self, ids: &[i32]) -> Result<BatchResponse<Locality>> {
+        batch::fetch_by_ids(
+            ids,
+            |chunk| {
+                let page_size = chunk.len() as i32;
+                self.localities_stream(LocalitiesQuery::new().ids(chunk).page_size(page_size))
+            },
+            |locality| locality.id,
+        )
+        .await
     }
 
     /// Get a specific locality by ID.
@@ -423,6 +937,11 @@ impl MindatClient {
         self.get(&format!("/localities/{}/", id)).await
     }
 
+    /// Get a specific locality by ID, with the response's metadata.
+    pub async fn locality_with_meta(&self, id: i32) -> Result<Response<Locality>> {
+        self.get_with_meta(&format!("/localities/{}/", id)).await
+    }
+
     // ==================== Locality Metadata ====================
 
     /// List locality ages.
@@ -432,7 +951,7 @@ impl MindatClient {
             #[serde(skip_serializing_if = "Option::is_none")]
             page: Option<i32>,
         }
-        self.get_with_query("/locality-age/", &Query { page }).await
+        self.get_page("/locality-age/", &Query { page }).await
     }
 
     /// Get a specific locality age by ID.
@@ -450,8 +969,7 @@ impl MindatClient {
             #[serde(skip_serializing_if = "Option::is_none")]
             page: Option<i32>,
         }
-        self.get_with_query("/locality-status/", &Query { page })
-            .await
+        self.get_page("/locality-status/", &Query { page }).await
     }
 
     /// Get a specific locality status by ID.
@@ -469,8 +987,7 @@ impl MindatClient {
             #[serde(skip_serializing_if = "Option::is_none")]
             page: Option<i32>,
         }
-        self.get_with_query("/locality-type/", &Query { page })
-            .await
+        self.get_page("/locality-type/", &Query { page }).await
     }
 
     /// Get a specific locality type by ID.
@@ -488,8 +1005,7 @@ impl MindatClient {
             #[serde(skip_serializing_if = "Option::is_none")]
             page: Option<i32>,
         }
-        self.get_with_query("/locgeoregion2/", &Query { page })
-            .await
+        self.get_page("/locgeoregion2/", &Query { page }).await
     }
 
     // ==================== IMA Minerals ====================
@@ -515,35 +1031,47 @@ impl MindatClient {
         &self,
         query: ImaMineralsQuery,
     ) -> Result<PaginatedResponse<ImaMaterial>> {
-        #[derive(serde::Serialize)]
-        struct QueryParams {
-            #[serde(skip_serializing_if = "Option::is_none")]
-            q: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            ima: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            updated_at: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            fields: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            omit: Option<String>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            page: Option<i32>,
-            #[serde(skip_serializing_if = "Option::is_none")]
-            page_size: Option<i32>,
-        }
+        self.get_page("/minerals-ima/", &QueryPairs::from(query))
+            .await
+    }
 
-        let params = QueryParams {
-            q: query.q,
-            ima: query.ima,
-            updated_at: query.updated_at,
-            fields: query.fields,
-            omit: query.omit,
-            page: query.page,
-            page_size: query.page_size,
-        };
+    /// List IMA-approved minerals, with the response's metadata.
+    pub async fn minerals_ima_with_meta(
+        &self,
+        query: ImaMineralsQuery,
+    ) -> Result<Response<PaginatedResponse<ImaMaterial>>> {
+        self.get_page_meta("/minerals-ima/", &QueryPairs::from(query))
+            .await
+    }
+
+    /// Stream IMA minerals matching the query, following pagination.
+    ///
+    /// See [`PageStream`] for limits and prefetching.
+    pub fn minerals_ima_stream(&self, query: ImaMineralsQuery) -> PageStream<ImaMaterial> {
+        let start = self.endpoint_url_with_query("/minerals-ima/", &QueryPairs::from(query));
+        PageStream::new::<PaginatedResponse<ImaMaterial>>(self.clone(), start)
+    }
+
+    /// Stream IMA minerals matching the query, decoding each result as soon
+    /// as it is received. See [`ItemStream`].
+    pub fn minerals_ima_items(&self, query: ImaMineralsQuery) -> ItemStream<ImaMaterial> {
+        let start = self.endpoint_url_with_query("/minerals-ima/", &QueryPairs::from(query));
+        ItemStream::new(self.clone(), start)
+    }
 
-        self.get_with_query("/minerals-ima/", &params).await
+    /// Get many IMA minerals by ID.
+    ///
+    /// Works like [`geomaterials_by_ids`](Self::geomaterials_by_ids).
+    pub async fn minerals_ima_by_ids(&self, ids: &[i32]) -> Result<BatchResponse<ImaMaterial>> {
+        batch::fetch_by_ids(
+            ids,
+            |chunk| {
+                let page_size = chunk.len() as i32;
+                self.minerals_ima_stream(ImaMineralsQuery::new().ids(chunk).page_size(page_size))
+            },
+            |mineral| mineral.id,
+        )
+        .await
     }
 
     /// Get a specific IMA mineral by ID.
@@ -594,29 +1122,366 @@ impl MindatClient {
     pub async fn photocount(&self) -> Result<serde_json::Value> {
         self.get("/photo-count/").await
     }
+
+    // ==================== Generic Requests ====================
+
+    /// Make a GET request to any endpoint and decode the response as `T`.
+    ///
+    /// Use this for endpoints or fields this crate does not model yet. The
+    /// request goes through the same authentication, retries, rate limiting,
+    /// caching, middleware and error handling as the typed methods. `path` is
+    /// relative to the base URL, and `query` is anything that serializes to
+    /// URL query parameters, such as a struct, a map or a slice of pairs; pass
+    /// `&()` for no parameters.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> mindat_rs::Result<()> {
+    /// use mindat_rs::{MindatClient, PaginatedResponse};
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Mineral {
+    ///     id: i32,
+    ///     name: Option<String>,
+    /// }
+    ///
+    /// let client = MindatClient::new("your-token");
+    /// let page: PaginatedResponse<Mineral> = client
+    ///     .request("/geomaterials/", &[("name", "quartz"), ("fields", "id,name")])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request<T, Q>(&self, path: &str, query: &Q) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+        Q: serde::Serialize + ?Sized,
+    {
+        self.get_with_query(path, query).await
+    }
+
+    /// Make a GET request to any endpoint and decode the response as `T`,
+    /// with the response's metadata.
+    ///
+    /// See [`request`](Self::request) and [`Response`].
+    pub async fn request_with_meta<T, Q>(&self, path: &str, query: &Q) -> Result<Response<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        Q: serde::Serialize + ?Sized,
+    {
+        self.get_with_query_meta(path, query).await
+    }
+
+    /// Make a GET request to any endpoint and return the JSON response as is.
+    ///
+    /// See [`request`](Self::request).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example() -> mindat_rs::Result<()> {
+    /// use mindat_rs::MindatClient;
+    ///
+    /// let client = MindatClient::new("your-token");
+    /// let quartz = client.request_raw("/geomaterials/3337/", &()).await?;
+    /// println!("{}", quartz["name"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request_raw<Q>(&self, path: &str, query: &Q) -> Result<serde_json::Value>
+    where
+        Q: serde::Serialize + ?Sized,
+    {
+        self.get_with_query(path, query).await
+    }
+}
+
+/// Query string parameters, in the order they are sent.
+///
+/// Multi-value parameters are either repeated (`?key=a&key=b`) or joined
+/// into a single comma-separated value (`?key=a,b`), depending on what the
+/// API expects for each of them.
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(transparent)]
+struct QueryPairs(Vec<(&'static str, String)>);
+
+impl QueryPairs {
+    /// Add a parameter if it is set.
+    fn one<V: ToString>(mut self, key: &'static str, value: Option<V>) -> Self {
+        if let Some(value) = value {
+            self.0.push((key, value.to_string()));
+        }
+        self
+    }
+
+    /// Add a multi-value parameter, repeating the key for every value.
+    fn each<V: ToString>(mut self, key: &'static str, values: Option<Vec<V>>) -> Self {
+        for value in values.into_iter().flatten() {
+            self.0.push((key, value.to_string()));
+        }
+        self
+    }
+
+    /// Add a multi-value parameter as a single comma-separated value.
+    fn joined<V: ToString>(self, key: &'static str, values: Option<Vec<V>>) -> Self {
+        let values = values.filter(|values| !values.is_empty()).map(|values| {
+            values
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        });
+        self.one(key, values)
+    }
+}
+
+impl From<GeomaterialsQuery> for QueryPairs {
+    fn from(query: GeomaterialsQuery) -> Self {
+        Self::default()
+            .one("name", query.name)
+            .one("q", query.q)
+            .one("ima", query.ima)
+            .each("ima_status", query.ima_status)
+            .each("ima_notes", query.ima_notes)
+            .each("entrytype", query.entrytype)
+            .one("elements_inc", query.elements_inc)
+            .one("elements_exc", query.elements_exc)
+            .each("crystal_system", query.crystal_system)
+            .each("cleavagetype", query.cleavagetype)
+            .each("fracturetype", query.fracturetype)
+            .each("lustretype", query.lustretype)
+            .each("diapheny", query.diapheny)
+            .each("tenacity", query.tenacity)
+            .one("colour", query.colour)
+            .one("streak", query.streak)
+            .one("opticaltype", query.opticaltype)
+            .one("opticalsign", query.opticalsign)
+            .one("hardness_min", query.hardness_min)
+            .one("hardness_max", query.hardness_max)
+            .one("density_min", query.density_min)
+            .one("density_max", query.density_max)
+            .one("ri_min", query.ri_min)
+            .one("ri_max", query.ri_max)
+            .one("bi_min", query.bi_min)
+            .one("bi_max", query.bi_max)
+            .one("optical2v_min", query.optical2v_min)
+            .one("optical2v_max", query.optical2v_max)
+            .one("varietyof", query.varietyof)
+            .one("synid", query.synid)
+            .one("polytypeof", query.polytypeof)
+            .one("groupid", query.groupid)
+            .joined("id__in", query.id_in)
+            .one("non_utf", query.non_utf)
+            .one("meteoritical_code", query.meteoritical_code)
+            .one("meteoritical_code_exists", query.meteoritical_code_exists)
+            .one("updated_at", query.updated_at)
+            .one("fields", query.fields)
+            .one("omit", query.omit)
+            .joined("expand", query.expand)
+            .one("ordering", query.ordering)
+            .one("page", query.page)
+            .one("page_size", query.page_size)
+    }
+}
+
+impl From<LocalitiesQuery> for QueryPairs {
+    fn from(query: LocalitiesQuery) -> Self {
+        Self::default()
+            .one("country", query.country)
+            .one("txt", query.txt)
+            .one("description", query.description)
+            .one("elements_inc", query.elements_inc)
+            .one("elements_exc", query.elements_exc)
+            .joined("id__in", query.id_in)
+            .one("updated_at", query.updated_at)
+            .one("fields", query.fields)
+            .one("omit", query.omit)
+            .joined("expand", query.expand)
+            .one("cursor", query.cursor)
+            .one("page_size", query.page_size)
+            .one("page", query.page)
+    }
+}
+
+impl From<ImaMineralsQuery> for QueryPairs {
+    fn from(query: ImaMineralsQuery) -> Self {
+        Self::default()
+            .one("q", query.q)
+            .one("ima", query.ima)
+            .joined("id__in", query.id_in)
+            .one("updated_at", query.updated_at)
+            .one("fields", query.fields)
+            .one("omit", query.omit)
+            .joined("expand", query.expand)
+            .one("page", query.page)
+            .one("page_size", query.page_size)
+    }
+}
+
+/// Wrap the error of a request that was attempted more than once, if the
+/// last failure was one worth retrying.
+///
+/// Errors such as a 404 are returned as they are, even after earlier
+/// transient failures, so callers can match on them.
+fn retries_exhausted(e: MindatError, attempts: u32) -> MindatError {
+    if attempts > 1 && e.is_retryable() {
+        MindatError::RetriesExhausted {
+            attempts,
+            source: Box::new(e),
+        }
+    } else {
+        e
+    }
+}
+
+/// Remove the headers making a request conditional, returning whether there
+/// were any.
+fn remove_conditions(headers: &mut HeaderMap) -> bool {
+    let etag = headers.remove(IF_NONE_MATCH).is_some();
+    let modified = headers.remove(IF_MODIFIED_SINCE).is_some();
+    etag || modified
+}
+
+/// Decode a successful response, reporting where decoding failed.
+fn decode<T: serde::de::DeserializeOwned>(
+    response: &TransportResponse,
+    mode: DecodeMode,
+) -> Result<(T, Vec<SkippedRecord>)> {
+    decode::from_slice(mode, &response.body)
+        .map_err(|e| MindatError::deserialization(response, e.path, e.source))
+}
+
+/// TLS implementation used for HTTPS connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TlsBackend {
+    #[cfg(feature = "native-tls")]
+    Native,
+    #[cfg(feature = "rustls-tls")]
+    Rustls,
 }
 
 /// Builder for MindatClient configuration.
+///
+/// # Example
+///
+/// ```no_run
+/// use mindat_rs::MindatClient;
+/// use std::time::Duration;
+///
+/// let client = MindatClient::builder()
+///     .token("your-token")
+///     .timeout(Duration::from_secs(60))
+///     .connect_timeout(Duration::from_secs(5))
+///     .user_agent("my-app/1.0")
+///     .header("X-Request-Source", "batch-job")?
+///     .build()?;
+/// # Ok::<(), mindat_rs::MindatError>(())
+/// ```
 #[derive(Debug, Clone)]
 pub struct MindatClientBuilder {
-    token: Option<String>,
+    credentials: Option<Arc<dyn CredentialProvider>>,
     base_url: String,
-    timeout: Option<std::time::Duration>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    user_agent: String,
+    headers: HeaderMap,
+    proxy: Option<Proxy>,
+    no_proxy: bool,
+    pool_max_idle_per_host: usize,
+    pool_idle_timeout: Option<Duration>,
+    tls: Option<TlsBackend>,
+    transport: Option<Arc<dyn Transport>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    retry: RetryPolicy,
+    rate_limit: Option<RateLimit>,
+    cache: Option<CacheConfig>,
+    #[cfg(feature = "disk-cache")]
+    disk_cache: Option<DiskCacheConfig>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<(PathBuf, CassetteMode)>,
+    coalesce: bool,
+    decode_mode: DecodeMode,
 }
 
 impl MindatClientBuilder {
     /// Create a new builder.
+    ///
+    /// Defaults to a 30s request timeout, a 10s connect timeout, a
+    /// browser-like User-Agent and up to 5 idle connections per host.
     pub fn new() -> Self {
         Self {
-            token: None,
+            credentials: None,
             base_url: DEFAULT_BASE_URL.to_string(),
-            timeout: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: HeaderMap::new(),
+            proxy: None,
+            no_proxy: false,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout: None,
+            tls: None,
+            transport: None,
+            middleware: Vec::new(),
+            retry: RetryPolicy::none(),
+            rate_limit: None,
+            cache: None,
+            #[cfg(feature = "disk-cache")]
+            disk_cache: None,
+            #[cfg(feature = "cassette")]
+            cassette: None,
+            coalesce: false,
+            decode_mode: DecodeMode::Default,
         }
     }
 
     /// Set the API token.
-    pub fn token(mut self, token: impl Into<String>) -> Self {
-        self.token = Some(token.into());
+    pub fn token(mut self, token: impl Into<SecretToken>) -> Self {
+        self.credentials = Some(Arc::new(StaticToken::new(token)));
+        self
+    }
+
+    /// Get the token for each request from a [`CredentialProvider`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mindat_rs::MindatClient;
+    /// use mindat_rs::credentials::EnvProvider;
+    ///
+    /// // Reads MINDAT_API_KEY
+    /// let client = MindatClient::builder().credentials(EnvProvider::new()).build()?;
+    /// # Ok::<(), mindat_rs::MindatError>(())
+    /// ```
+    pub fn credentials(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.credentials = Some(Arc::new(provider));
+        self
+    }
+
+    /// Apply the settings of a configuration [`Profile`].
+    ///
+    /// Settings missing from the profile are left unchanged. The cache
+    /// directory is only used with the `disk-cache` feature.
+    pub fn profile(mut self, profile: &Profile) -> Self {
+        if let Some(token) = &profile.token {
+            self = self.token(token.clone());
+        }
+        if let Some(base_url) = &profile.base_url {
+            self.base_url = base_url.clone();
+        }
+        if let Some(timeout) = profile.timeout {
+            self.timeout = Some(timeout);
+        }
+        if let Some(timeout) = profile.connect_timeout {
+            self.connect_timeout = Some(timeout);
+        }
+        #[cfg(feature = "disk-cache")]
+        if let Some(dir) = &profile.cache_dir {
+            self.disk_cache = Some(DiskCacheConfig::new(dir));
+        }
         self
     }
 
@@ -626,30 +1491,289 @@ impl MindatClientBuilder {
         self
     }
 
-    /// Set request timeout.
-    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+    /// Set the total request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
         self
     }
 
-    /// Build the client.
-    pub fn build(self) -> Result<MindatClient> {
-        let mut client_builder = Client::builder();
+    /// Set the timeout for establishing a connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
 
-        if let Some(timeout) = self.timeout {
-            client_builder = client_builder.timeout(timeout);
-        }
+    /// Set the timeout for each read from the connection.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the User-Agent header sent with every request.
+    ///
+    /// Defaults to [`DEFAULT_USER_AGENT`], a browser-like value that avoids
+    /// Cloudflare blocks.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Add a header sent with every request.
+    ///
+    /// Returns an error if the name or value is not a valid header.
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| MindatError::InvalidParameter(format!("Invalid header name: {}", name)))?;
+        let value = HeaderValue::from_str(value).map_err(|_| {
+            MindatError::InvalidParameter(format!("Invalid value for header {}", name))
+        })?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Add headers sent with every request.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Route requests through an HTTP(S) proxy.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use mindat_rs::MindatClient;
+    ///
+    /// let client = MindatClient::builder()
+    ///     .proxy(reqwest::Proxy::https("http://proxy.example.com:8080")?)
+    ///     .build()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Ignore proxies configured through environment variables.
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Set the maximum number of idle connections kept per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// Set how long idle connections are kept in the pool.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Use the platform's native TLS implementation.
+    ///
+    /// Requires the `native-tls` feature (enabled by default).
+    #[cfg(feature = "native-tls")]
+    pub fn use_native_tls(mut self) -> Self {
+        self.tls = Some(TlsBackend::Native);
+        self
+    }
 
-        let http = client_builder.build().map_err(MindatError::Request)?;
+    /// Use rustls for TLS.
+    ///
+    /// Requires the `rustls-tls` feature.
+    #[cfg(feature = "rustls-tls")]
+    pub fn use_rustls_tls(mut self) -> Self {
+        self.tls = Some(TlsBackend::Rustls);
+        self
+    }
+
+    /// Send requests through a custom [`Transport`].
+    ///
+    /// Replaces the default `reqwest` transport, so the timeout, proxy, pool
+    /// and TLS options of this builder no longer apply. Headers, the token,
+    /// retries and rate limiting still do.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Add a [`Middleware`] to the end of the chain.
+    ///
+    /// Middleware runs for every attempt of every request, after the caches
+    /// have been consulted and before the transport is called.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Set the retry policy for failed requests.
+    ///
+    /// Requests are not retried unless a policy is configured.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Limit the request rate and concurrency of the client.
+    ///
+    /// The limit is shared by every clone of the built client; requests
+    /// wait for a permit rather than failing.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Cache successful responses in memory.
+    ///
+    /// The cache is shared by every clone of the built client. See
+    /// [`CacheConfig`] for expiry and revalidation.
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Persist successful responses in a directory on disk.
+    ///
+    /// See [`DiskCacheConfig`] for read-through, write-through and offline
+    /// modes. Can be combined with the in-memory [`cache`](Self::cache),
+    /// which is consulted first.
+    #[cfg(feature = "disk-cache")]
+    pub fn disk_cache(mut self, config: DiskCacheConfig) -> Self {
+        self.disk_cache = Some(config);
+        self
+    }
+
+    /// Share one HTTP request between identical concurrent requests.
+    ///
+    /// While a request for a URL is in flight, further requests for the same
+    /// URL and token made through this client or its clones wait for it and
+    /// receive a copy of its response. Requests answered by a cache are not
+    /// affected. If the shared request fails, the waiting callers get a copy
+    /// of its error; errors of the HTTP client itself are copied as a
+    /// [`MindatError::Transport`] with the same message.
+    pub fn coalesce_requests(mut self) -> Self {
+        self.coalesce = true;
+        self
+    }
+
+    /// Set how response bodies that do not match the models are handled.
+    ///
+    /// See [`DecodeMode`]. Defaults to [`DecodeMode::Default`].
+    pub fn decode_mode(mut self, mode: DecodeMode) -> Self {
+        self.decode_mode = mode;
+        self
+    }
+
+    /// Record responses to, or replay them from, a cassette file.
+    ///
+    /// When recording, requests are sent through the transport the builder
+    /// would otherwise use, and the file is written when the client is
+    /// dropped or by [`MindatClient::save_cassette`]. See
+    /// [`cassette`](crate::cassette).
+    #[cfg(feature = "cassette")]
+    pub fn cassette(mut self, path: impl Into<PathBuf>, mode: CassetteMode) -> Self {
+        self.cassette = Some((path.into(), mode));
+        self
+    }
+
+    /// Build the client.
+    pub fn build(self) -> Result<MindatClient> {
+        let transport: Arc<dyn Transport> = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => Arc::new(ReqwestTransport::new(self.build_http()?)),
+        };
+        #[cfg(feature = "cassette")]
+        let cassette = match &self.cassette {
+            Some((path, CassetteMode::Record)) => Some(Arc::new(Cassette::recording(
+                path.clone(),
+                transport.clone(),
+            ))),
+            Some((path, CassetteMode::Replay)) => Some(Arc::new(Cassette::replay(path)?)),
+            None => None,
+        };
+        #[cfg(feature = "cassette")]
+        let transport: Arc<dyn Transport> = match &cassette {
+            Some(cassette) => cassette.clone(),
+            None => transport,
+        };
 
         let base_url = Url::parse(&self.base_url)?;
 
+        let mut default_headers = HeaderMap::new();
+        default_headers.insert(
+            USER_AGENT,
+            HeaderValue::from_str(&self.user_agent)
+                .map_err(|_| MindatError::InvalidParameter("Invalid User-Agent".to_string()))?,
+        );
+        default_headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        default_headers.extend(self.headers);
+
         Ok(MindatClient {
-            http,
+            transport,
+            middleware: self.middleware,
             base_url,
-            token: self.token,
+            default_headers,
+            credentials: self.credentials,
+            retry: self.retry,
+            limiter: self
+                .rate_limit
+                .map(|limit| Arc::new(RateLimiter::new(&limit))),
+            cache: self
+                .cache
+                .map(|config| Arc::new(ResponseCache::new(config))),
+            #[cfg(feature = "disk-cache")]
+            disk_cache: match self.disk_cache {
+                Some(config) => Some(Arc::new(DiskCache::new(config)?)),
+                None => None,
+            },
+            coalescer: self.coalesce.then(|| Arc::new(Coalescer::default())),
+            decode_mode: self.decode_mode,
+            #[cfg(feature = "cassette")]
+            cassette,
         })
     }
+
+    /// Build the `reqwest` client used by the default transport.
+    fn build_http(&self) -> Result<Client> {
+        let mut client_builder = Client::builder()
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout);
+
+        if let Some(timeout) = self.timeout {
+            client_builder = client_builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            client_builder = client_builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            client_builder = client_builder.read_timeout(timeout);
+        }
+        if self.no_proxy {
+            client_builder = client_builder.no_proxy();
+        }
+        if let Some(proxy) = self.proxy.clone() {
+            client_builder = client_builder.proxy(proxy);
+        }
+        let tls = self.tls;
+        match tls {
+            #[cfg(feature = "native-tls")]
+            Some(TlsBackend::Native) => client_builder = client_builder.use_native_tls(),
+            #[cfg(feature = "rustls-tls")]
+            Some(TlsBackend::Rustls) => client_builder = client_builder.use_rustls_tls(),
+            None => {}
+        }
+
+        client_builder.build().map_err(MindatError::Request)
+    }
+
+    /// Build a blocking client.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<crate::blocking::MindatClient> {
+        crate::blocking::MindatClient::from_async(self.build()?)
+    }
 }
 
 impl Default for MindatClientBuilder {
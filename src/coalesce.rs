@@ -0,0 +1,156 @@
+//! Single-flight coalescing of identical concurrent requests.
+//!
+//! The first request for a URL and token becomes the leader and is sent as
+//! usual. Identical requests made while it is in flight wait for the leader
+//! and receive a copy of its response, or of its error, instead of sending
+//! their own. If the leader is cancelled before it finishes, the waiting
+//! requests are sent separately.
+
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use url::Url;
+
+use crate::credentials::Fingerprint;
+use crate::error::{MindatError, Result};
+use crate::transport::TransportResponse;
+
+/// A response together with the number of attempts it took.
+pub(crate) type Exchange = (TransportResponse, u32);
+
+type Outcome = std::result::Result<Exchange, Arc<MindatError>>;
+
+/// Identity of a request for coalescing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FlightKey {
+    url: String,
+    credentials: Option<Fingerprint>,
+}
+
+/// Requests currently in flight, shared by every clone of a client.
+#[derive(Debug, Default)]
+pub(crate) struct Coalescer {
+    flights: Mutex<HashMap<FlightKey, watch::Sender<Option<Outcome>>>>,
+}
+
+/// The role of a request in a flight.
+pub(crate) enum Flight<'a> {
+    /// Send the request and [`finish`](Leader::finish) with the result.
+    Leader(Leader<'a>),
+    /// Wait for the leader's result.
+    Follower(watch::Receiver<Option<Outcome>>),
+}
+
+impl Coalescer {
+    /// Lead a new flight for a request, or join the one in progress.
+    pub(crate) fn join(&self, url: &Url, headers: &HeaderMap) -> Flight<'_> {
+        let key = FlightKey {
+            url: url.to_string(),
+            credentials: Fingerprint::of(headers),
+        };
+        let mut flights = self.flights.lock().expect("coalescer lock poisoned");
+        if let Some(sender) = flights.get(&key) {
+            return Flight::Follower(sender.subscribe());
+        }
+        flights.insert(key.clone(), watch::channel(None).0);
+        Flight::Leader(Leader {
+            coalescer: self,
+            key: Some(key),
+        })
+    }
+}
+
+/// The request that is actually sent; ends the flight when dropped.
+pub(crate) struct Leader<'a> {
+    coalescer: &'a Coalescer,
+    key: Option<FlightKey>,
+}
+
+impl Leader<'_> {
+    /// Hand the result to the waiting requests and return it.
+    ///
+    /// The leader keeps its own error; the waiting requests each get a
+    /// [duplicate](MindatError::duplicate) of it.
+    pub(crate) fn finish(mut self, result: Result<Exchange>) -> Result<Exchange> {
+        let Some(sender) = self.end() else {
+            return result;
+        };
+        if sender.receiver_count() == 0 {
+            return result;
+        }
+        let outcome = match &result {
+            Ok(exchange) => Ok(exchange.clone()),
+            Err(e) => Err(Arc::new(e.duplicate())),
+        };
+        sender.send_replace(Some(outcome));
+        result
+    }
+
+    /// Remove the flight, so later requests start a new one.
+    fn end(&mut self) -> Option<watch::Sender<Option<Outcome>>> {
+        let key = self.key.take()?;
+        self.coalescer
+            .flights
+            .lock()
+            .expect("coalescer lock poisoned")
+            .remove(&key)
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the followers without a result
+        self.end();
+    }
+}
+
+/// Wait for the leader's result, or `None` if it was cancelled.
+pub(crate) async fn wait(
+    mut receiver: watch::Receiver<Option<Outcome>>,
+) -> Option<Result<Exchange>> {
+    let outcome = receiver.wait_for(Option::is_some).await.ok()?.clone()?;
+    Some(outcome.map_err(|e| e.duplicate()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url() -> Url {
+        Url::parse("https://api.mindat.org/v1/geomaterials/3337/").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_followers_get_copy_of_leader_error() {
+        let coalescer = Coalescer::default();
+        let Flight::Leader(leader) = coalescer.join(&url(), &HeaderMap::new()) else {
+            panic!("first request should lead");
+        };
+        let Flight::Follower(receiver) = coalescer.join(&url(), &HeaderMap::new()) else {
+            panic!("second request should follow");
+        };
+
+        let err = MindatError::Transport("connection reset".into());
+        let err = leader.finish(Err(err)).unwrap_err();
+        assert!(matches!(err, MindatError::Transport(_)));
+        let copy = wait(receiver).await.expect("leader finished").unwrap_err();
+        assert!(matches!(copy, MindatError::Transport(_)));
+        assert_eq!(copy.to_string(), err.to_string());
+        assert!(matches!(
+            coalescer.join(&url(), &HeaderMap::new()),
+            Flight::Leader(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leader_releases_followers() {
+        let coalescer = Coalescer::default();
+        let leader = coalescer.join(&url(), &HeaderMap::new());
+        let Flight::Follower(receiver) = coalescer.join(&url(), &HeaderMap::new()) else {
+            panic!("second request should follow");
+        };
+        drop(leader);
+        assert!(wait(receiver).await.is_none());
+    }
+}
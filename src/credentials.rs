@@ -0,0 +1,439 @@
+//! API credentials and configuration profiles.
+//!
+//! A [`CredentialProvider`] supplies the API token sent with each request.
+//! Besides a fixed token ([`StaticToken`]) the token can come from an
+//! environment variable ([`EnvProvider`]), a profile in the configuration
+//! file ([`ConfigFileProvider`]) or any function ([`CallbackProvider`]).
+//!
+//! The configuration file lives at `$XDG_CONFIG_HOME/mindat/config.toml`
+//! (usually `~/.config/mindat/config.toml`, or `%APPDATA%\mindat\config.toml`
+//! on Windows) and holds named profiles:
+//!
+//! ```toml
+//! [default]
+//! token = "your-api-token"
+//!
+//! [work]
+//! token = "another-token"
+//! base_url = "https://mindat-mirror.example.com/v1/"
+//! timeout = 60          # seconds
+//! connect_timeout = 5   # seconds
+//! cache_dir = "/var/cache/mindat"
+//! ```
+//!
+//! Tokens are held in a [`SecretToken`], which is wiped from memory when
+//! dropped and never shown by `Debug`.
+//!
+//! Use a profile with [`MindatClient::from_profile`](crate::MindatClient::from_profile)
+//! or [`MindatClientBuilder::profile`](crate::MindatClientBuilder::profile).
+
+use reqwest::header::{AUTHORIZATION, HeaderMap};
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+use crate::error::{MindatError, Result};
+
+/// Environment variable read by [`EnvProvider::new`].
+pub const TOKEN_ENV_VAR: &str = "MINDAT_API_KEY";
+
+/// Name of the profile used by
+/// [`MindatClient::from_profile`](crate::MindatClient::from_profile) when
+/// none is given.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// An API token that is zeroed on drop and redacted in `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretToken(Zeroizing<String>);
+
+impl SecretToken {
+    /// Wrap a token.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(Zeroizing::new(token.into()))
+    }
+
+    /// The token itself. Take care not to log it.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretToken {
+    fn from(token: String) -> Self {
+        Self::new(token)
+    }
+}
+
+impl From<&str> for SecretToken {
+    fn from(token: &str) -> Self {
+        Self::new(token)
+    }
+}
+
+impl From<&String> for SecretToken {
+    fn from(token: &String) -> Self {
+        Self::new(token.as_str())
+    }
+}
+
+impl fmt::Debug for SecretToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretToken(<redacted>)")
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretToken {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// SHA-256 of the `Authorization` header of a request.
+///
+/// Responses are kept apart by the credentials they were fetched with; a
+/// fingerprint identifies the credentials without keeping a copy of the
+/// token.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Fingerprint of the credentials in `headers`, or `None` for an
+    /// anonymous request.
+    pub(crate) fn of(headers: &HeaderMap) -> Option<Self> {
+        let value = headers.get(AUTHORIZATION)?;
+        Some(Self(Sha256::digest(value.as_bytes()).into()))
+    }
+
+    /// The fingerprint as lowercase hex.
+    #[cfg_attr(not(feature = "disk-cache"), allow(dead_code))]
+    pub(crate) fn to_hex(self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Fingerprint({:02x}{:02x}{:02x}{:02x}..)",
+            self.0[0], self.0[1], self.0[2], self.0[3]
+        )
+    }
+}
+
+/// Supplies the API token for requests.
+///
+/// The provider is asked for the token before every request, so it may
+/// rotate tokens; it should be cheap to call and cache expensive lookups.
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    /// Get the token, or `None` to make the request without authentication.
+    fn token(&self) -> Result<Option<SecretToken>>;
+}
+
+/// A fixed token.
+#[derive(Debug, Clone)]
+pub struct StaticToken(SecretToken);
+
+impl StaticToken {
+    /// Use `token` for every request.
+    pub fn new(token: impl Into<SecretToken>) -> Self {
+        Self(token.into())
+    }
+}
+
+impl CredentialProvider for StaticToken {
+    fn token(&self) -> Result<Option<SecretToken>> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+/// Reads the token from an environment variable.
+///
+/// Requests are made without authentication while the variable is unset.
+#[derive(Debug, Clone)]
+pub struct EnvProvider {
+    var: String,
+}
+
+impl EnvProvider {
+    /// Read the token from `MINDAT_API_KEY`.
+    pub fn new() -> Self {
+        Self::var(TOKEN_ENV_VAR)
+    }
+
+    /// Read the token from the given environment variable.
+    pub fn var(name: impl Into<String>) -> Self {
+        Self { var: name.into() }
+    }
+}
+
+impl Default for EnvProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialProvider for EnvProvider {
+    fn token(&self) -> Result<Option<SecretToken>> {
+        Ok(std::env::var(&self.var)
+            .ok()
+            .filter(|token| !token.is_empty())
+            .map(SecretToken::new))
+    }
+}
+
+/// Gets the token by calling a function.
+///
+/// The function may return anything convertible into a [`SecretToken`],
+/// such as a `String`.
+///
+/// # Example
+///
+/// ```no_run
+/// use mindat_rs::MindatClient;
+/// use mindat_rs::credentials::CallbackProvider;
+///
+/// let client = MindatClient::builder()
+///     .credentials(CallbackProvider::new(|| Ok(Some(read_token_from_vault()))))
+///     .build()?;
+/// # fn read_token_from_vault() -> String { String::new() }
+/// # Ok::<(), mindat_rs::MindatError>(())
+/// ```
+pub struct CallbackProvider<F> {
+    callback: F,
+}
+
+impl<F, T> CallbackProvider<F>
+where
+    F: Fn() -> Result<Option<T>> + Send + Sync,
+    T: Into<SecretToken>,
+{
+    /// Call `callback` for the token before each request.
+    pub fn new(callback: F) -> Self {
+        Self { callback }
+    }
+}
+
+impl<F> fmt::Debug for CallbackProvider<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackProvider").finish_non_exhaustive()
+    }
+}
+
+impl<F, T> CredentialProvider for CallbackProvider<F>
+where
+    F: Fn() -> Result<Option<T>> + Send + Sync,
+    T: Into<SecretToken>,
+{
+    fn token(&self) -> Result<Option<SecretToken>> {
+        Ok((self.callback)()?.map(Into::into))
+    }
+}
+
+/// Reads the token from a profile in the configuration file.
+///
+/// The file is read once, when the provider is created.
+#[derive(Debug, Clone)]
+pub struct ConfigFileProvider {
+    token: Option<SecretToken>,
+}
+
+impl ConfigFileProvider {
+    /// Use the token of `profile` in the default configuration file.
+    pub fn new(profile: &str) -> Result<Self> {
+        Ok(Self {
+            token: ConfigFile::load()?.profile(profile)?.token.clone(),
+        })
+    }
+
+    /// Use the token of `profile` in the configuration file at `path`.
+    pub fn from_path(path: impl AsRef<Path>, profile: &str) -> Result<Self> {
+        Ok(Self {
+            token: ConfigFile::load_from(path)?.profile(profile)?.token.clone(),
+        })
+    }
+}
+
+impl CredentialProvider for ConfigFileProvider {
+    fn token(&self) -> Result<Option<SecretToken>> {
+        Ok(self.token.clone())
+    }
+}
+
+/// Settings of one profile in the configuration file.
+///
+/// Unknown settings are rejected, so a misspelt name is reported instead of
+/// being ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// API token.
+    pub token: Option<SecretToken>,
+    /// Base URL of the API.
+    pub base_url: Option<String>,
+    /// Total request timeout, given in seconds.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub timeout: Option<Duration>,
+    /// Connection timeout, given in seconds.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub connect_timeout: Option<Duration>,
+    /// Directory for the on-disk response cache (`disk-cache` feature).
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// The configuration file: a set of named [`Profile`]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct ConfigFile {
+    profiles: HashMap<String, Profile>,
+}
+
+impl ConfigFile {
+    /// Location of the configuration file:
+    /// `$XDG_CONFIG_HOME/mindat/config.toml`, falling back to
+    /// `%APPDATA%\mindat\config.toml` on Windows and
+    /// `$HOME/.config/mindat/config.toml` elsewhere.
+    pub fn default_path() -> Option<PathBuf> {
+        let env_dir = |name: &str| {
+            std::env::var_os(name)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+        };
+        let config_home = env_dir("XDG_CONFIG_HOME").or_else(|| {
+            if cfg!(windows) {
+                env_dir("APPDATA")
+            } else {
+                env_dir("HOME").map(|home| home.join(".config"))
+            }
+        })?;
+        Some(config_home.join("mindat").join("config.toml"))
+    }
+
+    /// Load the configuration file from its default location.
+    pub fn load() -> Result<Self> {
+        let path = Self::default_path().ok_or_else(|| {
+            MindatError::Config("cannot locate the configuration directory".to_string())
+        })?;
+        Self::load_from(path)
+    }
+
+    /// Load a configuration file.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| MindatError::Config(format!("cannot read {}: {}", path.display(), e)))?;
+        Self::parse(&contents)
+    }
+
+    /// Parse the contents of a configuration file.
+    pub fn parse(contents: &str) -> Result<Self> {
+        toml::from_str(contents).map_err(|e| MindatError::Config(e.to_string()))
+    }
+
+    /// Get a profile by name.
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| MindatError::Config(format!("no profile named '{}'", name)))
+    }
+
+    /// Names of all profiles.
+    pub fn profile_names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+}
+
+/// Deserialize a number of seconds into a `Duration`.
+fn deserialize_seconds<'de, D>(deserializer: D) -> std::result::Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds = Option::<f64>::deserialize(deserializer)?;
+    seconds
+        .map(|seconds| Duration::try_from_secs_f64(seconds).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profiles() {
+        let config = ConfigFile::parse(
+            r#"
+            [default]
+            token = "abc"
+
+            [work]
+            token = "def"
+            base_url = "https://mirror.example.com/v1/"
+            timeout = 60
+            connect_timeout = 2.5
+            cache_dir = "/tmp/mindat"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.profile("default").unwrap().token,
+            Some(SecretToken::new("abc"))
+        );
+        let work = config.profile("work").unwrap();
+        assert_eq!(
+            work.base_url.as_deref(),
+            Some("https://mirror.example.com/v1/")
+        );
+        assert_eq!(work.timeout, Some(Duration::from_secs(60)));
+        assert_eq!(work.connect_timeout, Some(Duration::from_millis(2500)));
+        assert_eq!(work.cache_dir, Some(PathBuf::from("/tmp/mindat")));
+
+        let mut names: Vec<_> = config.profile_names().collect();
+        names.sort();
+        assert_eq!(names, ["default", "work"]);
+    }
+
+    #[test]
+    fn test_missing_profile_is_config_error() {
+        let config = ConfigFile::parse("[default]\ntoken = \"abc\"\n").unwrap();
+        assert!(matches!(
+            config.profile("work"),
+            Err(MindatError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_file_is_config_error() {
+        assert!(matches!(
+            ConfigFile::parse("[default]\ntimeout = \"soon\"\n"),
+            Err(MindatError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_setting_is_config_error() {
+        let result = ConfigFile::parse("[default]\ntoken = \"abc\"\nbase_ulr = \"x\"\n");
+        let Err(MindatError::Config(message)) = result else {
+            panic!("expected a config error, got {:?}", result);
+        };
+        assert!(message.contains("base_ulr"), "{}", message);
+    }
+
+    #[test]
+    fn test_secret_token_debug_is_redacted() {
+        let token = SecretToken::new("abc123");
+        assert_eq!(token.expose(), "abc123");
+        assert!(!format!("{:?}", token).contains("abc123"));
+        assert!(!format!("{:?}", StaticToken::new("abc123")).contains("abc123"));
+    }
+
+    #[test]
+    fn test_env_provider() {
+        let provider = EnvProvider::var("MINDAT_RS_TEST_UNSET_TOKEN_VAR");
+        assert_eq!(provider.token().unwrap(), None);
+    }
+}
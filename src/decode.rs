@@ -0,0 +1,386 @@
+//! How response bodies are matched against the models.
+//!
+//! By default fields the models do not know about are ignored, or kept in
+//! the `extra` map of [`Geomaterial`](crate::Geomaterial),
+//! [`Locality`](crate::Locality) and [`ImaMaterial`](crate::ImaMaterial),
+//! while a value of the wrong type fails the whole response. A
+//! [`DecodeMode`] set with
+//! [`MindatClientBuilder::decode_mode`](crate::MindatClientBuilder::decode_mode)
+//! changes this:
+//!
+//! - [`DecodeMode::Strict`] rejects responses with unknown fields, to detect
+//!   changes to the API's schema, for example in CI.
+//! - [`DecodeMode::Lenient`] skips results of a page that fail to decode and
+//!   reports them in [`Response::skipped`](crate::response::Response::skipped)
+//!   instead of failing the page.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example() -> mindat_rs::Result<()> {
+//! use mindat_rs::decode::DecodeMode;
+//! use mindat_rs::{GeomaterialsQuery, MindatClient};
+//!
+//! let client = MindatClient::builder()
+//!     .token("your-token")
+//!     .decode_mode(DecodeMode::Lenient)
+//!     .build()?;
+//!
+//! let response = client.geomaterials_with_meta(GeomaterialsQuery::new()).await?;
+//! for skipped in &response.skipped {
+//!     eprintln!("skipped {:?} at {}: {}", skipped.id, skipped.path, skipped.message);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::de::{DeserializeOwned, Deserializer, Error as _};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cell::Cell;
+
+thread_local! {
+    /// Whether a body is being decoded in strict mode on this thread.
+    ///
+    /// serde gives a flattened field such as `extra` no access to the
+    /// deserializer of its struct, so the mode cannot be passed down to it.
+    /// Decoding is synchronous, so [`extra`] reads the mode from here.
+    static STRICT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as decoding in strict mode until dropped.
+struct StrictGuard(bool);
+
+impl StrictGuard {
+    fn enter() -> Self {
+        Self(STRICT.with(|strict| strict.replace(true)))
+    }
+}
+
+impl Drop for StrictGuard {
+    fn drop(&mut self) {
+        STRICT.with(|strict| strict.set(self.0));
+    }
+}
+
+/// How response bodies that do not match the models are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Ignore unknown fields, or keep them in `extra` where a model has it;
+    /// fail the response if a value cannot be decoded.
+    #[default]
+    Default,
+    /// Fail the response if it has a field the models do not know about.
+    Strict,
+    /// Skip results of a page that cannot be decoded, reporting them in
+    /// [`Response::skipped`](crate::response::Response::skipped).
+    Lenient,
+}
+
+/// A result skipped in [lenient](DecodeMode::Lenient) mode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedRecord {
+    /// Position of the result in its page.
+    pub index: usize,
+    /// The result's `id` field, if it has one.
+    pub id: Option<i64>,
+    /// Path of the value that failed to decode, e.g. `results[3].hardness_min`.
+    pub path: String,
+    /// Why the value could not be decoded.
+    pub message: String,
+}
+
+/// A body that could not be decoded.
+#[derive(Debug)]
+pub(crate) struct DecodeError {
+    /// Path of the value that failed to decode.
+    pub(crate) path: String,
+    pub(crate) source: serde_json::Error,
+}
+
+/// Decode a JSON body in the given mode, together with the results skipped
+/// in lenient mode.
+pub(crate) fn from_slice<T: DeserializeOwned>(
+    mode: DecodeMode,
+    body: &[u8],
+) -> Result<(T, Vec<SkippedRecord>), DecodeError> {
+    match mode {
+        DecodeMode::Default => Ok((decode(body)?, Vec::new())),
+        DecodeMode::Strict => Ok((strict(body)?, Vec::new())),
+        DecodeMode::Lenient => lenient(body),
+    }
+}
+
+/// Decode a JSON body, reporting where decoding failed.
+fn decode<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| DecodeError {
+        path: e.path().to_string(),
+        source: e.into_inner(),
+    })?;
+    deserializer.end().map_err(|source| DecodeError {
+        path: ".".to_string(),
+        source,
+    })?;
+    Ok(value)
+}
+
+/// Decode a JSON body, failing on fields `T` does not know about.
+fn strict<T: DeserializeOwned>(body: &[u8]) -> Result<T, DecodeError> {
+    let _strict = StrictGuard::enter();
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let mut unknown = None;
+    let mut record = |path: serde_ignored::Path| {
+        unknown.get_or_insert_with(|| unknown_field(&path));
+    };
+    let ignored = serde_ignored::Deserializer::new(&mut deserializer, &mut record);
+    let value = serde_path_to_error::deserialize(ignored).map_err(|e| DecodeError {
+        path: e.path().to_string(),
+        source: e.into_inner(),
+    })?;
+    deserializer.end().map_err(|source| DecodeError {
+        path: ".".to_string(),
+        source,
+    })?;
+    match unknown {
+        Some(error) => Err(error),
+        None => Ok(value),
+    }
+}
+
+/// Decode a body, skipping the results of a page that fail to decode.
+///
+/// The body is parsed once. Each result is then decoded on its own, inside
+/// the rest of the page, and the page is decoded with the results that
+/// succeeded.
+fn lenient<T: DeserializeOwned>(body: &[u8]) -> Result<(T, Vec<SkippedRecord>), DecodeError> {
+    let mut page: Value = serde_json::from_slice(body).map_err(|source| DecodeError {
+        path: ".".to_string(),
+        source,
+    })?;
+    let results = match page.get_mut("results") {
+        Some(Value::Array(results)) => std::mem::take(results),
+        _ => return Ok((from_value(&page)?, Vec::new())),
+    };
+
+    let mut kept = Vec::with_capacity(results.len());
+    let mut skipped = Vec::new();
+    for (index, result) in results.into_iter().enumerate() {
+        page["results"] = Value::Array(vec![result]);
+        let error = from_value::<T>(&page).err();
+        let result = match page["results"].take() {
+            Value::Array(mut probe) => probe.pop().expect("probe holds one result"),
+            _ => unreachable!("probe results replaced"),
+        };
+        let Some(error) = error else {
+            kept.push(result);
+            continue;
+        };
+        match result_at(&error.path) {
+            Some((0, rest)) => skipped.push(SkippedRecord {
+                index,
+                id: result.get("id").and_then(Value::as_i64),
+                path: record_path(index, rest),
+                message: error.source.to_string(),
+            }),
+            // The rest of the page is at fault
+            _ => return Err(error),
+        }
+    }
+
+    page["results"] = Value::Array(kept);
+    Ok((from_value(&page)?, skipped))
+}
+
+/// Decode a parsed body, reporting where decoding failed.
+fn from_value<T: DeserializeOwned>(value: &Value) -> Result<T, DecodeError> {
+    serde_path_to_error::deserialize(value).map_err(|e| DecodeError {
+        path: e.path().to_string(),
+        source: e.into_inner(),
+    })
+}
+
+/// Split a path within the `results` of a page into the index of the
+/// result and the path within it.
+fn result_at(path: &str) -> Option<(usize, &str)> {
+    let (index, rest) = path.strip_prefix("results[")?.split_once(']')?;
+    let rest = match rest.strip_prefix('.') {
+        Some(rest) => rest,
+        None if rest.is_empty() => ".",
+        None => return None,
+    };
+    Some((index.parse().ok()?, rest))
+}
+
+/// Path of the `index`th result of a page, given the path of the failing
+/// value within the result.
+pub(crate) fn record_path(index: usize, path: &str) -> String {
+    match path {
+        "." => format!("results[{index}]"),
+        path => format!("results[{index}].{path}"),
+    }
+}
+
+/// Decode the `index`th result of a page in lenient mode.
+pub(crate) fn record<'de, T: Deserialize<'de>>(
+    index: usize,
+    value: Value,
+) -> Result<T, SkippedRecord> {
+    let id = value.get("id").and_then(Value::as_i64);
+    serde_path_to_error::deserialize(value).map_err(|e| SkippedRecord {
+        index,
+        id,
+        path: record_path(index, &e.path().to_string()),
+        message: e.into_inner().to_string(),
+    })
+}
+
+/// Error for a field ignored in strict mode.
+fn unknown_field(path: &serde_ignored::Path) -> DecodeError {
+    let (parent, key) = match path {
+        serde_ignored::Path::Map { parent, key } => (*parent, key.as_str()),
+        path => (path, ""),
+    };
+    let mut location = String::new();
+    write_path(parent, &mut location);
+    if location.is_empty() {
+        location.push('.');
+    }
+    DecodeError {
+        path: location,
+        source: serde_json::Error::custom(format!("unknown field `{key}`")),
+    }
+}
+
+/// Write `path` in the same notation as `serde_path_to_error`.
+fn write_path(path: &serde_ignored::Path, out: &mut String) {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => {}
+        Path::Seq { parent, index } => {
+            write_path(parent, out);
+            out.push_str(&format!("[{index}]"));
+        }
+        Path::Map { parent, key } => {
+            write_path(parent, out);
+            if !out.is_empty() {
+                out.push('.');
+            }
+            out.push_str(key);
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => write_path(parent, out),
+    }
+}
+
+/// Deserialize the unmodeled fields of a model, rejecting them in strict
+/// mode.
+pub(crate) fn extra<'de, D>(deserializer: D) -> Result<Map<String, Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let extra = Map::deserialize(deserializer)?;
+    if STRICT.with(Cell::get) {
+        if let Some(key) = extra.keys().next() {
+            return Err(D::Error::custom(format!("unknown field `{key}`")));
+        }
+    }
+    Ok(extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Geomaterial, PaginatedResponse};
+
+    const PAGE: &str = r#"{
+        "count": 2,
+        "next": null,
+        "previous": null,
+        "results": [
+            {"id": 1, "name": "Quartz", "colour_code": "white"},
+            {"id": "two", "name": "Calcite"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_default_mode_keeps_extra_fields() {
+        let (mineral, _): (Geomaterial, _) =
+            from_slice(DecodeMode::Default, br#"{"id": 1, "colour_code": "white"}"#).unwrap();
+        assert_eq!(mineral.extra["colour_code"], "white");
+
+        let err =
+            from_slice::<PaginatedResponse<Geomaterial>>(DecodeMode::Default, PAGE.as_bytes())
+                .unwrap_err();
+        assert_eq!(err.path, "results[1].id");
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_unknown_fields() {
+        let err = from_slice::<Geomaterial>(DecodeMode::Strict, br#"{"id": 1, "colour_code": 2}"#)
+            .unwrap_err();
+        assert_eq!(err.path, ".");
+        assert!(
+            err.source
+                .to_string()
+                .contains("unknown field `colour_code`")
+        );
+
+        let err = from_slice::<PaginatedResponse<crate::models::Country>>(
+            DecodeMode::Strict,
+            br#"{"count": 1, "results": [{"id": 1, "text": "Norway", "continent": "EU",
+                "iso": "NO", "latdir": "N", "longdir": "E", "flag": 1}]}"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.path, "results[0]");
+        assert_eq!(err.source.to_string(), "unknown field `flag`");
+    }
+
+    #[test]
+    fn test_lenient_mode_skips_bad_records() {
+        let (page, skipped): (PaginatedResponse<Geomaterial>, _) =
+            from_slice(DecodeMode::Lenient, PAGE.as_bytes()).unwrap();
+        assert_eq!(page.results.len(), 1);
+        assert_eq!(page.results[0].extra["colour_code"], "white");
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].index, 1);
+        assert_eq!(skipped[0].id, None);
+        assert_eq!(skipped[0].path, "results[1].id");
+    }
+
+    #[test]
+    fn test_strict_mode_ends_with_the_decode() {
+        let body = br#"{"id": 1, "name": "Quartz", "crystal_data": {"a": 4.9}}"#;
+        let err = from_slice::<Geomaterial>(DecodeMode::Strict, body).unwrap_err();
+        assert!(
+            err.source
+                .to_string()
+                .starts_with("unknown field `crystal_data`")
+        );
+
+        let (value, _): (Value, _) = from_slice(DecodeMode::Strict, body).unwrap();
+        assert_eq!(value["crystal_data"], serde_json::json!({"a": 4.9}));
+        let (mineral, _): (Geomaterial, _) = from_slice(DecodeMode::Default, body).unwrap();
+        assert_eq!(mineral.extra["crystal_data"]["a"], 4.9);
+    }
+
+    #[test]
+    fn test_lenient_mode_reports_page_errors() {
+        let body = br#"{"count": "two", "results": [{"id": 1}, {"id": "x"}]}"#;
+        let err =
+            from_slice::<PaginatedResponse<Geomaterial>>(DecodeMode::Lenient, body).unwrap_err();
+        assert_eq!(err.path, "count");
+    }
+
+    #[test]
+    fn test_result_at() {
+        assert_eq!(
+            result_at("results[3].hardness_min"),
+            Some((3, "hardness_min"))
+        );
+        assert_eq!(result_at("results[0]"), Some((0, ".")));
+        assert_eq!(result_at("results[0]x"), None);
+        assert_eq!(result_at("count"), None);
+    }
+}
@@ -0,0 +1,308 @@
+//! Persistent on-disk response cache.
+//!
+//! Enabled with the `disk-cache` cargo feature. A [`DiskCacheConfig`]
+//! configured on the [`MindatClientBuilder`](crate::MindatClientBuilder)
+//! stores successful responses as gzip-compressed files in a directory, so
+//! data fetched while online remains available without connectivity.
+//!
+//! Responses are keyed by their normalized URL: the query parameters are
+//! sorted, so the same query always maps to the same file regardless of the
+//! order its parameters were given in. The key also holds a SHA-256
+//! fingerprint of the request's credentials, so clients with different
+//! tokens, or none, can share a directory without seeing each other's
+//! responses. The token itself is never written to disk.
+
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+use crate::credentials::Fingerprint;
+use crate::error::{HtmlPage, MindatError, Result};
+use crate::transport::TransportResponse;
+
+/// How the client uses the disk cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiskCacheMode {
+    /// Serve cached responses when available, otherwise fetch from the API
+    /// and store the response.
+    #[default]
+    ReadThrough,
+    /// Always fetch from the API and store every response, keeping the cache
+    /// up to date for later offline use.
+    WriteThrough,
+    /// Never contact the API. Requests missing from the cache fail with
+    /// [`MindatError::OfflineCacheMiss`].
+    OfflineOnly,
+}
+
+/// Disk cache configuration.
+///
+/// # Example
+///
+/// ```no_run
+/// use mindat_rs::MindatClient;
+/// use mindat_rs::disk_cache::{DiskCacheConfig, DiskCacheMode};
+///
+/// // Fill the cache while online...
+/// let online = MindatClient::builder()
+///     .token("your-token")
+///     .disk_cache(DiskCacheConfig::new("mindat-cache").mode(DiskCacheMode::WriteThrough))
+///     .build()?;
+///
+/// // ...and use it in the field.
+/// let offline = MindatClient::builder()
+///     .disk_cache(DiskCacheConfig::new("mindat-cache").mode(DiskCacheMode::OfflineOnly))
+///     .build()?;
+/// # Ok::<(), mindat_rs::MindatError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskCacheConfig {
+    dir: PathBuf,
+    mode: DiskCacheMode,
+    max_age: Option<Duration>,
+}
+
+impl DiskCacheConfig {
+    /// Cache responses in `dir`, which is created if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: DiskCacheMode::default(),
+            max_age: None,
+        }
+    }
+
+    /// Set how the cache is used. Defaults to [`DiskCacheMode::ReadThrough`].
+    pub fn mode(mut self, mode: DiskCacheMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// In read-through mode, fetch responses again once they are older than
+    /// `max_age`. Cached responses never expire by default, and offline mode
+    /// always uses them regardless of age.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+}
+
+/// Disk cache enforcing a [`DiskCacheConfig`].
+#[derive(Debug)]
+pub(crate) struct DiskCache {
+    config: DiskCacheConfig,
+}
+
+impl DiskCache {
+    /// Create the cache, making sure its directory exists.
+    pub(crate) fn new(config: DiskCacheConfig) -> Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        Ok(Self { config })
+    }
+
+    /// Look up a cached response for `url`, requested with `headers`.
+    ///
+    /// Returns `Ok(None)` when the request should go to the API, and
+    /// [`MindatError::OfflineCacheMiss`] when it cannot.
+    pub(crate) async fn load(
+        &self,
+        url: &Url,
+        headers: &HeaderMap,
+    ) -> Result<Option<TransportResponse>> {
+        let max_age = match self.config.mode {
+            DiskCacheMode::WriteThrough => return Ok(None),
+            DiskCacheMode::ReadThrough => self.config.max_age,
+            DiskCacheMode::OfflineOnly => None,
+        };
+
+        let key = entry_key(url, headers);
+        let path = self.path_for(&key);
+        let response = {
+            let key = key.clone();
+            let url = url.clone();
+            tokio::task::spawn_blocking(move || read_entry(&path, &key, max_age, url))
+                .await
+                .ok()
+                .flatten()
+        };
+
+        match response {
+            None if self.config.mode == DiskCacheMode::OfflineOnly => {
+                Err(MindatError::OfflineCacheMiss(normalize_url(url)))
+            }
+            response => Ok(response),
+        }
+    }
+
+    /// Store a successful response for `url`, requested with `headers`.
+    ///
+    /// Failing to write is not an error: the response is still returned to
+    /// the caller, it just won't be available offline.
+    pub(crate) async fn store(&self, url: &Url, headers: &HeaderMap, response: &TransportResponse) {
+        if response.status != StatusCode::OK || HtmlPage::detect(response).is_some() {
+            return;
+        }
+        let key = entry_key(url, headers);
+        let path = self.path_for(&key);
+        let body = response.body.clone();
+        let _ = tokio::task::spawn_blocking(move || write_entry(&path, &key, &body)).await;
+    }
+
+    /// File holding the response for an entry key.
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.config.dir.join(format!("{:016x}.json.gz", fnv1a(key)))
+    }
+}
+
+/// Read a cache file, returning `None` if it is missing, unreadable, too old
+/// or belongs to a different key.
+fn read_entry(
+    path: &Path,
+    key: &str,
+    max_age: Option<Duration>,
+    url: Url,
+) -> Option<TransportResponse> {
+    let file = File::open(path).ok()?;
+    if let Some(max_age) = max_age {
+        let age = file
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        if age > max_age {
+            return None;
+        }
+    }
+
+    let mut reader = BufReader::new(GzDecoder::new(file));
+    let mut stored_key = String::new();
+    reader.read_line(&mut stored_key).ok()?;
+    if stored_key.trim_end_matches('\n') != key {
+        return None;
+    }
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body).ok()?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Some(TransportResponse {
+        status: StatusCode::OK,
+        headers,
+        body: Bytes::from(body),
+        url,
+    })
+}
+
+/// Write a cache file: the entry key on the first line, then the body.
+///
+/// The file is written under a temporary name and renamed into place, so a
+/// reader never sees a partially written entry.
+fn write_entry(path: &Path, key: &str, body: &[u8]) -> std::io::Result<()> {
+    // Concurrent stores of the same URL each write their own file
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+    let tmp = path.with_extension(format!(
+        "tmp{}-{}",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = write_gzip(&tmp, key, body).and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Write the contents of a cache file to `path`.
+fn write_gzip(path: &Path, key: &str, body: &[u8]) -> std::io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+    encoder.write_all(key.as_bytes())?;
+    encoder.write_all(b"\n")?;
+    encoder.write_all(body)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Key of the entry for a request: the normalized URL, followed by the
+/// fingerprint of its credentials if it has any.
+fn entry_key(url: &Url, headers: &HeaderMap) -> String {
+    let url = normalize_url(url);
+    match Fingerprint::of(headers) {
+        Some(credentials) => format!("{} {}", url, credentials.to_hex()),
+        None => url,
+    }
+}
+
+/// Normalize a URL so equivalent requests share a cache entry.
+fn normalize_url(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    pairs.sort();
+
+    let mut normalized = url.clone();
+    normalized.set_fragment(None);
+    if pairs.is_empty() {
+        normalized.set_query(None);
+    } else {
+        normalized.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    normalized.to_string()
+}
+
+/// 64-bit FNV-1a hash, stable across platforms and releases.
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_url_sorts_query() {
+        let a = Url::parse("https://api.mindat.org/v1/geomaterials/?page=2&name=quartz").unwrap();
+        let b = Url::parse("https://api.mindat.org/v1/geomaterials/?name=quartz&page=2").unwrap();
+
+        assert_eq!(normalize_url(&a), normalize_url(&b));
+        assert_eq!(
+            normalize_url(&a),
+            "https://api.mindat.org/v1/geomaterials/?name=quartz&page=2"
+        );
+    }
+
+    #[test]
+    fn test_concurrent_writes_of_one_entry() {
+        let dir = std::env::temp_dir().join(format!("mindat-rs-writes-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("entry.json.gz");
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let path = &path;
+                scope.spawn(move || write_entry(path, "key", format!("[{i}]").as_bytes()).unwrap());
+            }
+        });
+
+        let url = Url::parse("https://api.mindat.org/v1/").unwrap();
+        let entry = read_entry(&path, "key", None, url).expect("entry written");
+        assert!(serde_json::from_slice::<Vec<u32>>(&entry.body).is_ok());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fnv1a_is_stable() {
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -1,7 +1,16 @@
 //! Error types for the Mindat API client.
 
+use reqwest::header::{CONTENT_TYPE, SERVER};
+use serde::de::Error as _;
+use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::transport::TransportResponse;
+
+/// Longest body excerpt kept in an error, in bytes.
+const SNIPPET_LEN: usize = 512;
+
 /// Errors that can occur when using the Mindat API client.
 #[derive(Error, Debug)]
 pub enum MindatError {
@@ -9,34 +18,466 @@ pub enum MindatError {
     #[error("HTTP request failed: {0}")]
     Request(#[from] reqwest::Error),
 
+    /// A custom transport failed to send the request
+    #[error("Transport error: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+
     /// Failed to parse URL
     #[error("Invalid URL: {0}")]
     Url(#[from] url::ParseError),
 
     /// API returned an error response
-    #[error("API error (status {status}): {message}")]
-    Api { status: u16, message: String },
+    #[error("API error (status {status}) from {}: {message}", details.url)]
+    Api {
+        status: u16,
+        /// Start of the response body.
+        message: String,
+        details: Box<ResponseDetails>,
+    },
 
     /// Failed to deserialize response
-    #[error("Failed to parse response: {0}")]
-    Deserialization(#[from] serde_json::Error),
+    #[error("Failed to parse response from {url} at `{path}`: {source}")]
+    Deserialization {
+        /// URL of the response.
+        url: String,
+        /// Path of the value that failed to parse, e.g. `results[3].hardness_min`.
+        path: String,
+        /// Start of the response body.
+        snippet: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// An HTML page came back instead of JSON, such as a Cloudflare
+    /// challenge or a proxy error page
+    #[error("{page} ({})", details)]
+    Html {
+        page: HtmlPage,
+        details: Box<ResponseDetails>,
+    },
 
     /// Authentication error - missing or invalid token
     #[error("Authentication required: please provide a valid API token")]
-    AuthenticationRequired,
+    AuthenticationRequired { details: Box<ResponseDetails> },
 
     /// Rate limit exceeded
     #[error("Rate limit exceeded, please wait before making more requests")]
-    RateLimited,
+    RateLimited {
+        /// How long the server asked to wait, from the `Retry-After` header.
+        retry_after: Option<Duration>,
+        details: Box<ResponseDetails>,
+    },
 
     /// Resource not found
-    #[error("Resource not found: {0}")]
-    NotFound(String),
+    #[error("Resource not found at {}: {message}", details.url)]
+    NotFound {
+        /// Start of the response body.
+        message: String,
+        details: Box<ResponseDetails>,
+    },
+
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Configuration file or profile could not be used
+    #[error("Configuration error: {0}")]
+    Config(String),
 
     /// Invalid parameter value
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
+
+    /// Offline mode is enabled and the response is not in the disk cache;
+    /// only returned with the `disk-cache` feature
+    #[error("No cached response for {0} while offline")]
+    OfflineCacheMiss(String),
+
+    /// Replaying a cassette and no response was recorded for the request;
+    /// only returned with the `cassette` feature
+    #[error("No response recorded for {0}")]
+    CassetteMiss(String),
+
+    /// Request kept failing after all retry attempts
+    ///
+    /// Only used when the last failure was itself retryable; a request that
+    /// ends with, say, a 404 after a retried 503 fails with
+    /// [`MindatError::NotFound`].
+    #[error("Request failed after {attempts} attempts: {source}")]
+    RetriesExhausted {
+        attempts: u32,
+        #[source]
+        source: Box<MindatError>,
+    },
+}
+
+/// Broad category of a [`MindatError`], for handling errors without matching
+/// on every variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The request could not be sent or the response could not be received.
+    Network,
+    /// The request timed out.
+    Timeout,
+    /// The API returned an error status.
+    Api,
+    /// The token is missing or invalid.
+    Authentication,
+    /// Too many requests were made.
+    RateLimited,
+    /// The requested resource does not exist.
+    NotFound,
+    /// Cloudflare challenged or blocked the request.
+    Blocked,
+    /// The response could not be decoded.
+    Decode,
+    /// The request or client was set up wrongly.
+    Configuration,
+    /// Reading or writing a local file failed.
+    Io,
+    /// No response is available offline, from the disk cache or a cassette.
+    Offline,
+}
+
+impl MindatError {
+    /// The category of this error. For [`MindatError::RetriesExhausted`] this
+    /// is the category of the last failure.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Request(e) if e.is_timeout() => ErrorKind::Timeout,
+            Self::Request(e) if e.is_builder() => ErrorKind::Configuration,
+            Self::Request(_) | Self::Transport(_) => ErrorKind::Network,
+            Self::Api { .. } => ErrorKind::Api,
+            Self::Html { page, .. } => match page {
+                HtmlPage::CloudflareChallenge | HtmlPage::CloudflareBlock => ErrorKind::Blocked,
+                HtmlPage::Other { .. } => ErrorKind::Api,
+            },
+            Self::Deserialization { .. } => ErrorKind::Decode,
+            Self::AuthenticationRequired { .. } => ErrorKind::Authentication,
+            Self::RateLimited { .. } => ErrorKind::RateLimited,
+            Self::NotFound { .. } => ErrorKind::NotFound,
+            Self::Io(_) => ErrorKind::Io,
+            Self::Url(_) | Self::Config(_) | Self::InvalidParameter(_) => ErrorKind::Configuration,
+            Self::OfflineCacheMiss(_) | Self::CassetteMiss(_) => ErrorKind::Offline,
+            Self::RetriesExhausted { source, .. } => source.kind(),
+        }
+    }
+
+    /// Returns true if the same request may succeed when made again later:
+    /// network failures, timeouts, rate limiting and 5xx responses.
+    ///
+    /// For [`MindatError::RetriesExhausted`] this describes the last failure.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Request(e) => !e.is_builder() && !e.is_decode(),
+            Self::Transport(_) | Self::RateLimited { .. } => true,
+            Self::Api { status, .. } => *status >= 500,
+            Self::Html { page, details } => match page {
+                HtmlPage::CloudflareChallenge => true,
+                HtmlPage::CloudflareBlock => false,
+                HtmlPage::Other { .. } => details.status >= 500,
+            },
+            Self::RetriesExhausted { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Details of the response that caused this error, if there was one.
+    pub fn response(&self) -> Option<&ResponseDetails> {
+        match self {
+            Self::Api { details, .. }
+            | Self::Html { details, .. }
+            | Self::RateLimited { details, .. } => Some(details),
+            Self::RetriesExhausted { source, .. } => source.response(),
+            _ => None,
+        }
+    }
+
+    /// A copy of this error, for handing one failure to several callers.
+    ///
+    /// Errors of the HTTP client and of custom transports cannot be copied
+    /// and become a [`MindatError::Transport`] with the same message; I/O and
+    /// parse errors keep their message but lose their position and source.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Self::Request(e) => Self::Transport(e.to_string().into()),
+            Self::Transport(e) => Self::Transport(e.to_string().into()),
+            Self::Url(e) => Self::Url(*e),
+            Self::Api {
+                status,
+                message,
+                details,
+            } => Self::Api {
+                status: *status,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Self::Deserialization {
+                url,
+                path,
+                snippet,
+                source,
+            } => Self::Deserialization {
+                url: url.clone(),
+                path: path.clone(),
+                snippet: snippet.clone(),
+                source: serde_json::Error::custom(source),
+            },
+            Self::Html { page, details } => Self::Html {
+                page: page.clone(),
+                details: details.clone(),
+            },
+            Self::AuthenticationRequired { details } => Self::AuthenticationRequired {
+                details: details.clone(),
+            },
+            Self::RateLimited {
+                retry_after,
+                details,
+            } => Self::RateLimited {
+                retry_after: *retry_after,
+                details: details.clone(),
+            },
+            Self::NotFound { message, details } => Self::NotFound {
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Self::Io(e) => Self::Io(std::io::Error::new(e.kind(), e.to_string())),
+            Self::Config(message) => Self::Config(message.clone()),
+            Self::InvalidParameter(message) => Self::InvalidParameter(message.clone()),
+            Self::OfflineCacheMiss(url) => Self::OfflineCacheMiss(url.clone()),
+            Self::CassetteMiss(url) => Self::CassetteMiss(url.clone()),
+            Self::RetriesExhausted { attempts, source } => Self::RetriesExhausted {
+                attempts: *attempts,
+                source: Box::new(source.duplicate()),
+            },
+        }
+    }
+
+    /// Error for a response that could not be decoded.
+    pub(crate) fn deserialization(
+        response: &TransportResponse,
+        path: String,
+        source: serde_json::Error,
+    ) -> Self {
+        Self::Deserialization {
+            url: response.url.to_string(),
+            path,
+            snippet: snippet(&response.body),
+            source,
+        }
+    }
+}
+
+/// Information about an error response, for diagnosing failures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseDetails {
+    /// URL of the response.
+    pub url: String,
+    /// HTTP status code.
+    pub status: u16,
+    /// Request ID assigned by the server (`X-Request-Id`, or Cloudflare's
+    /// `CF-Ray`), useful when reporting problems.
+    pub request_id: Option<String>,
+    /// Delay requested by the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+impl ResponseDetails {
+    pub(crate) fn new(response: &TransportResponse) -> Self {
+        let header = |name: &str| {
+            response
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            url: response.url.to_string(),
+            status: response.status.as_u16(),
+            request_id: header("x-request-id").or_else(|| header("cf-ray")),
+            retry_after: crate::retry::retry_after(&response.headers),
+        }
+    }
+}
+
+impl fmt::Display for ResponseDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "status {} from {}", self.status, self.url)?;
+        if let Some(id) = &self.request_id {
+            write!(f, " (request ID {})", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// The kind of HTML page received in place of a JSON response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlPage {
+    /// A Cloudflare "checking your browser" challenge, served when traffic
+    /// looks automated.
+    CloudflareChallenge,
+    /// A Cloudflare block page, served when the client is denied access.
+    CloudflareBlock,
+    /// Any other HTML page, e.g. a proxy or server error page.
+    Other {
+        /// Text of the page's `<title>`, if it has one.
+        title: Option<String>,
+    },
+}
+
+impl HtmlPage {
+    /// Recognize an HTML page in a response, using its Content-Type and, when
+    /// that is missing, the start of the body.
+    pub(crate) fn detect(response: &TransportResponse) -> Option<Self> {
+        let content_type = response
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let body = String::from_utf8_lossy(&response.body);
+        let is_html = if content_type.is_empty() {
+            body.trim_start().starts_with('<')
+        } else {
+            content_type.contains("html")
+        };
+        if !is_html {
+            return None;
+        }
+
+        let mitigated = response
+            .headers
+            .get("cf-mitigated")
+            .and_then(|value| value.to_str().ok());
+        if mitigated == Some("challenge")
+            || body.contains("challenge-platform")
+            || body.contains("Just a moment...")
+        {
+            return Some(Self::CloudflareChallenge);
+        }
+
+        let from_cloudflare = response
+            .headers
+            .get(SERVER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|server| server.eq_ignore_ascii_case("cloudflare"));
+        if body.contains("cf-error-details")
+            || body.contains("Attention Required! | Cloudflare")
+            || (from_cloudflare && response.status.as_u16() == 403)
+        {
+            return Some(Self::CloudflareBlock);
+        }
+
+        Some(Self::Other {
+            title: html_title(&body),
+        })
+    }
+}
+
+impl fmt::Display for HtmlPage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CloudflareChallenge => f.write_str(
+                "Cloudflare challenged the request as automated traffic; \
+                 reduce the request rate and retry later",
+            ),
+            Self::CloudflareBlock => f.write_str(
+                "Cloudflare blocked the request; \
+                 your IP address or network may be denied access to the Mindat API",
+            ),
+            Self::Other { title: Some(title) } => {
+                write!(f, "Received an HTML page instead of JSON: \"{}\"", title)
+            }
+            Self::Other { title: None } => f.write_str("Received an HTML page instead of JSON"),
+        }
+    }
+}
+
+/// Text of the `<title>` element of an HTML page.
+fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then(|| title.chars().take(100).collect())
+}
+
+/// Start of a response body, cut at [`SNIPPET_LEN`] bytes.
+pub(crate) fn snippet(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    if text.len() <= SNIPPET_LEN {
+        return text.into_owned();
+    }
+    let mut end = SNIPPET_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &text[..end])
 }
 
 /// Result type alias for Mindat operations.
 pub type Result<T> = std::result::Result<T, MindatError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_truncates_on_char_boundary() {
+        assert_eq!(snippet(b"short"), "short");
+        let long = "é".repeat(SNIPPET_LEN);
+        let cut = snippet(long.as_bytes());
+        assert!(cut.ends_with('…'));
+        assert!(cut.len() <= SNIPPET_LEN + '…'.len_utf8());
+    }
+
+    #[test]
+    fn test_html_title() {
+        assert_eq!(
+            html_title("<html><head><TITLE>\n  502 Bad\n Gateway </TITLE></head>"),
+            Some("502 Bad Gateway".to_string())
+        );
+        assert_eq!(html_title("<html><body>oops</body></html>"), None);
+    }
+
+    #[test]
+    fn test_retries_exhausted_reports_last_failure() {
+        let err = MindatError::RetriesExhausted {
+            attempts: 3,
+            source: Box::new(MindatError::NotFound {
+                message: "Not found.".to_string(),
+                details: Box::new(ResponseDetails {
+                    url: "https://api.mindat.org/v1/geomaterials/1/".to_string(),
+                    status: 404,
+                    request_id: None,
+                    retry_after: None,
+                }),
+            }),
+        };
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_duplicate_keeps_variant_and_message() {
+        let err = MindatError::RetriesExhausted {
+            attempts: 2,
+            source: Box::new(MindatError::Io(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "slow disk",
+            ))),
+        };
+        let copy = err.duplicate();
+        assert!(matches!(
+            &copy,
+            MindatError::RetriesExhausted { attempts: 2, source }
+                if matches!(**source, MindatError::Io(ref e) if e.kind() == std::io::ErrorKind::TimedOut)
+        ));
+        assert_eq!(copy.to_string(), err.to_string());
+    }
+}
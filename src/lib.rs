@@ -10,9 +10,17 @@
 //!
 //! - Full coverage of the Mindat API endpoints
 //! - Strongly-typed request builders and response models
-//! - Async/await support using tokio
-//! - Pagination helpers
-//! - Comprehensive error handling
+//! - A [`MindatApi`] trait over the endpoints, for fakes and decorators
+//! - Async/await support using tokio, plus an optional blocking client
+//!   (`blocking` feature)
+//! - Pagination helpers and auto-paginating streams
+//! - Comprehensive error handling with configurable retries
+//! - Credentials from environment variables, callbacks or configuration
+//!   profiles
+//! - Response caching in memory, or on disk for offline use (`disk-cache`
+//!   feature)
+//! - An in-process fake of the API for testing code that uses the client
+//!   (`testing` feature)
 //!
 //! ## Quick Start
 //!
@@ -79,6 +87,34 @@
 //! # }
 //! ```
 //!
+//! Or let a stream follow the pages for you:
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use mindat_rs::{MindatClient, GeomaterialsQuery};
+//!
+//! # async fn example() -> mindat_rs::Result<()> {
+//! let client = MindatClient::new("token");
+//!
+//! let mut minerals = client
+//!     .geomaterials_stream(GeomaterialsQuery::new().page_size(100))
+//!     .max_items(250);
+//! while let Some(mineral) = minerals.next().await {
+//!     println!("{:?}", mineral?.name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Observability
+//!
+//! With the `tracing` feature every request runs in a `mindat.request` span
+//! recording the endpoint, query string, status, latency, attempts and result
+//! count. With the `metrics` feature the same measurements are reported
+//! through the [`metrics`](https://docs.rs/metrics) facade as
+//! `mindat_requests_total`, `mindat_request_duration_seconds`,
+//! `mindat_request_retries_total` and `mindat_request_results`.
+//!
 //! ## Available Endpoints
 //!
 //! - **Countries**: List and retrieve country information
@@ -88,13 +124,46 @@
 //! - **Classification**: Dana 8th ed. and Nickel-Strunz 10th ed. systems
 //! - **Locality Metadata**: Ages, statuses, types, and geographic regions
 
+pub mod api;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+#[cfg(feature = "cassette")]
+pub mod cassette;
 pub mod client;
+mod coalesce;
+pub mod credentials;
+pub mod decode;
+#[cfg(feature = "disk-cache")]
+pub mod disk_cache;
 pub mod error;
+pub mod middleware;
 pub mod models;
+pub mod rate_limit;
+pub mod response;
+pub mod retry;
+mod split;
+pub mod stream;
+mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transport;
 
-pub use client::{DEFAULT_BASE_URL, MindatClient, MindatClientBuilder};
-pub use error::{MindatError, Result};
+pub use api::MindatApi;
+pub use batch::BatchResponse;
+pub use cache::{CacheConfig, CacheStats};
+pub use client::{DEFAULT_BASE_URL, DEFAULT_USER_AGENT, MindatClient, MindatClientBuilder};
+pub use credentials::{CredentialProvider, SecretToken};
+pub use decode::{DecodeMode, SkippedRecord};
+pub use error::{ErrorKind, HtmlPage, MindatError, ResponseDetails, Result};
+pub use middleware::Middleware;
 pub use models::*;
+pub use rate_limit::RateLimit;
+pub use response::Response;
+pub use retry::{RetryPolicy, RetryableErrorKind};
+pub use stream::{ItemStream, PageStream};
+pub use transport::{StreamingResponse, Transport, TransportRequest, TransportResponse};
 
 #[cfg(test)]
 mod tests {
@@ -106,6 +175,30 @@ mod tests {
         assert_eq!(client.base_url().as_str(), "https://api.mindat.org/v1/");
     }
 
+    #[test]
+    fn test_builder_rejects_invalid_header() {
+        assert!(
+            MindatClient::builder()
+                .header("X-Bad", "line\nbreak")
+                .is_err()
+        );
+        assert!(
+            MindatClient::builder()
+                .header("bad header", "value")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_builder_with_proxy() {
+        let client = MindatClient::builder()
+            .proxy(reqwest::Proxy::https("http://127.0.0.1:3128").unwrap())
+            .pool_idle_timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to build client");
+        assert_eq!(client.base_url().as_str(), DEFAULT_BASE_URL);
+    }
+
     #[test]
     fn test_anonymous_client() {
         let client = MindatClient::anonymous();
@@ -172,4 +265,17 @@ mod tests {
         assert_eq!(GeomaterialsOrdering::IdDesc.to_string(), "-id");
         assert_eq!(GeomaterialsOrdering::Name.to_string(), "name");
     }
+
+    #[test]
+    fn test_query_enum_display() {
+        assert_eq!(CrystalSystem::Hexagonal.to_string(), "Hexagonal");
+        assert_eq!(CleavageType::DistinctGood.to_string(), "Distinct/Good");
+        assert_eq!(Tenacity::VeryBrittle.to_string(), "very brittle");
+        assert_eq!(OpticalSign::Both.to_string(), "+/-");
+        assert_eq!(
+            ImaStatus::PendingPublication.to_string(),
+            "PENDING_PUBLICATION"
+        );
+        assert_eq!(EntryType::Rock.to_string(), "7");
+    }
 }
@@ -0,0 +1,150 @@
+//! Request and response middleware.
+//!
+//! [`Middleware`] registered with
+//! [`MindatClientBuilder::middleware`](crate::MindatClientBuilder::middleware)
+//! sees every request the client sends and every response it receives, and
+//! may modify them. Typical uses are adding correlation IDs, logging failed
+//! responses or rewriting URLs to point at a mirror.
+//!
+//! Middleware runs once per attempt, so a retried request passes through the
+//! chain again. `before_request` hooks run in registration order and
+//! `after_response` hooks in reverse order.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use mindat_rs::middleware::Middleware;
+//! use mindat_rs::transport::TransportRequest;
+//! use mindat_rs::{MindatClient, Result, TransportResponse};
+//! use reqwest::header::HeaderValue;
+//!
+//! /// Tags every request with a correlation ID.
+//! #[derive(Debug)]
+//! struct CorrelationId(&'static str);
+//!
+//! impl Middleware for CorrelationId {
+//!     fn before_request(&self, request: &mut TransportRequest) -> Result<Option<TransportResponse>> {
+//!         request
+//!             .headers
+//!             .insert("X-Correlation-Id", HeaderValue::from_static(self.0));
+//!         Ok(None)
+//!     }
+//! }
+//!
+//! let client = MindatClient::builder()
+//!     .token("your-token")
+//!     .middleware(CorrelationId("batch-42"))
+//!     .build()?;
+//! # Ok::<(), mindat_rs::MindatError>(())
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::transport::{StreamingResponse, Transport, TransportRequest, TransportResponse};
+
+/// Hooks run around every request sent by the client.
+///
+/// Both hooks default to doing nothing. Returning an error from either hook
+/// fails the request with that error.
+pub trait Middleware: fmt::Debug + Send + Sync {
+    /// Inspect or modify a request before it is sent.
+    ///
+    /// Return `Some(response)` to skip sending the request and use the given
+    /// response instead. Later middleware is then skipped as well.
+    fn before_request(&self, request: &mut TransportRequest) -> Result<Option<TransportResponse>> {
+        let _ = request;
+        Ok(None)
+    }
+
+    /// Inspect or modify a response, including non-success ones, before the
+    /// client handles it.
+    ///
+    /// Successful pages streamed by an
+    /// [`ItemStream`](crate::stream::ItemStream) are never held in memory as
+    /// a whole, so this hook does not see them unless a `before_request` hook
+    /// produced the response. It does see streamed error responses and HTML
+    /// pages, which are read whole.
+    fn after_response(
+        &self,
+        request: &TransportRequest,
+        response: &mut TransportResponse,
+    ) -> Result<()> {
+        let _ = (request, response);
+        Ok(())
+    }
+}
+
+impl<M: Middleware + ?Sized> Middleware for Arc<M> {
+    fn before_request(&self, request: &mut TransportRequest) -> Result<Option<TransportResponse>> {
+        (**self).before_request(request)
+    }
+
+    fn after_response(
+        &self,
+        request: &TransportRequest,
+        response: &mut TransportResponse,
+    ) -> Result<()> {
+        (**self).after_response(request, response)
+    }
+}
+
+/// Send a request through the middleware chain and the transport.
+pub(crate) async fn send(
+    transport: &dyn Transport,
+    middleware: &[Arc<dyn Middleware>],
+    mut request: TransportRequest,
+) -> Result<TransportResponse> {
+    let mut ran = 0;
+    let mut synthetic = None;
+    for layer in middleware {
+        ran += 1;
+        if let Some(response) = layer.before_request(&mut request)? {
+            synthetic = Some(response);
+            break;
+        }
+    }
+
+    let mut response = match synthetic {
+        Some(response) => response,
+        None => transport.send(request.clone()).await?,
+    };
+
+    for layer in middleware[..ran].iter().rev() {
+        layer.after_response(&request, &mut response)?;
+    }
+    Ok(response)
+}
+
+/// Send a request through the middleware chain and the transport without
+/// reading the body of a successful response.
+///
+/// Error responses and HTML pages are read whole and passed to the
+/// `after_response` hooks; successful responses are only passed to them when
+/// produced by a `before_request` hook.
+pub(crate) async fn send_streaming(
+    transport: &dyn Transport,
+    middleware: &[Arc<dyn Middleware>],
+    mut request: TransportRequest,
+) -> Result<StreamingResponse> {
+    for (ran, layer) in middleware.iter().enumerate() {
+        if let Some(mut response) = layer.before_request(&mut request)? {
+            for layer in middleware[..=ran].iter().rev() {
+                layer.after_response(&request, &mut response)?;
+            }
+            return Ok(response.into());
+        }
+    }
+    let response = transport.send_streaming(request.clone()).await?;
+    if response.is_streamable() {
+        return Ok(response);
+    }
+
+    // Error responses and HTML pages are small, so read them whole
+    let mut response = response.collect().await?;
+    for layer in middleware.iter().rev() {
+        layer.after_response(&request, &mut response)?;
+    }
+    Ok(response.into())
+}
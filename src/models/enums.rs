@@ -266,3 +266,31 @@ impl std::fmt::Display for GeomaterialsOrdering {
         write!(f, "{}", s)
     }
 }
+
+/// Implement `Display` using each variant's serialized name, which is also
+/// the value the API expects in query strings.
+macro_rules! display_serialized {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl std::fmt::Display for $ty {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    self.serialize(f)
+                }
+            }
+        )*
+    };
+}
+
+display_serialized!(
+    CrystalSystem,
+    CleavageType,
+    Diapheny,
+    EntryType,
+    FractureType,
+    LustreType,
+    Tenacity,
+    OpticalType,
+    OpticalSign,
+    ImaStatus,
+    ImaNotes,
+);
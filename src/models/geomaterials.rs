@@ -361,6 +361,13 @@ pub struct Geomaterial {
     /// Type localities.
     #[serde(default, deserialize_with = "deserialize_optional_vec_i32")]
     pub type_localities: Option<Vec<i32>>,
+    /// Fields returned by the API that this model does not cover.
+    #[serde(
+        flatten,
+        deserialize_with = "crate::decode::extra",
+        skip_serializing_if = "serde_json::Map::is_empty"
+    )]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Builder for geomaterial query parameters.
@@ -484,6 +491,18 @@ impl GeomaterialsQuery {
         self
     }
 
+    /// Filter by IMA status (any of).
+    pub fn ima_statuses(mut self, statuses: Vec<ImaStatus>) -> Self {
+        self.ima_status = Some(statuses);
+        self
+    }
+
+    /// Filter by IMA notes (any of).
+    pub fn ima_notes(mut self, notes: Vec<ImaNotes>) -> Self {
+        self.ima_notes = Some(notes);
+        self
+    }
+
     /// Filter by included elements.
     pub fn with_elements(mut self, elements: impl Into<String>) -> Self {
         self.elements_inc = Some(elements.into());
@@ -502,6 +521,48 @@ impl GeomaterialsQuery {
         self
     }
 
+    /// Filter by cleavage type (all of).
+    pub fn cleavage_types(mut self, types: Vec<CleavageType>) -> Self {
+        self.cleavagetype = Some(types);
+        self
+    }
+
+    /// Filter by fracture type (all of).
+    pub fn fracture_types(mut self, types: Vec<FractureType>) -> Self {
+        self.fracturetype = Some(types);
+        self
+    }
+
+    /// Filter by lustre type (all of).
+    pub fn lustre_types(mut self, types: Vec<LustreType>) -> Self {
+        self.lustretype = Some(types);
+        self
+    }
+
+    /// Filter by transparency (all of).
+    pub fn diapheny(mut self, diapheny: Vec<Diapheny>) -> Self {
+        self.diapheny = Some(diapheny);
+        self
+    }
+
+    /// Filter by tenacity (all of).
+    pub fn tenacity(mut self, tenacity: Vec<Tenacity>) -> Self {
+        self.tenacity = Some(tenacity);
+        self
+    }
+
+    /// Filter by optical type.
+    pub fn optical_type(mut self, optical_type: OpticalType) -> Self {
+        self.opticaltype = Some(optical_type);
+        self
+    }
+
+    /// Filter by optical sign.
+    pub fn optical_sign(mut self, sign: OpticalSign) -> Self {
+        self.opticalsign = Some(sign);
+        self
+    }
+
     /// Filter by hardness range.
     pub fn hardness_range(mut self, min: f32, max: f32) -> Self {
         self.hardness_min = Some(min);
@@ -516,6 +577,12 @@ impl GeomaterialsQuery {
         self
     }
 
+    /// Filter by IDs.
+    pub fn ids(mut self, ids: Vec<i32>) -> Self {
+        self.id_in = Some(ids);
+        self
+    }
+
     /// Select specific fields.
     pub fn select_fields(mut self, fields: impl Into<String>) -> Self {
         self.fields = Some(fields.into());
@@ -123,6 +123,13 @@ pub struct Locality {
     /// Geomaterials at this locality (when expanded).
     #[serde(default, deserialize_with = "deserialize_optional_vec_i32")]
     pub geomaterials: Option<Vec<i32>>,
+    /// Fields returned by the API that this model does not cover.
+    #[serde(
+        flatten,
+        deserialize_with = "crate::decode::extra",
+        skip_serializing_if = "serde_json::Map::is_empty"
+    )]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Builder for locality query parameters.
@@ -192,6 +199,12 @@ impl LocalitiesQuery {
         self
     }
 
+    /// Filter by IDs.
+    pub fn ids(mut self, ids: Vec<i32>) -> Self {
+        self.id_in = Some(ids);
+        self
+    }
+
     /// Select specific fields.
     pub fn select_fields(mut self, fields: impl Into<String>) -> Self {
         self.fields = Some(fields.into());
@@ -51,6 +51,13 @@ pub struct ImaMaterial {
     /// Mindat formula note.
     #[serde(default)]
     pub mindat_formula_note: Option<String>,
+    /// Fields returned by the API that this model does not cover.
+    #[serde(
+        flatten,
+        deserialize_with = "crate::decode::extra",
+        skip_serializing_if = "serde_json::Map::is_empty"
+    )]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Builder for IMA minerals query parameters.
@@ -88,6 +95,18 @@ impl ImaMineralsQuery {
         self
     }
 
+    /// IMA filter.
+    pub fn ima(mut self, ima: i32) -> Self {
+        self.ima = Some(ima);
+        self
+    }
+
+    /// Filter by IDs.
+    pub fn ids(mut self, ids: Vec<i32>) -> Self {
+        self.id_in = Some(ids);
+        self
+    }
+
     /// Select specific fields.
     pub fn select_fields(mut self, fields: impl Into<String>) -> Self {
         self.fields = Some(fields.into());
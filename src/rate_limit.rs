@@ -0,0 +1,244 @@
+//! Client-side rate limiting.
+//!
+//! A [`RateLimit`] configured on the
+//! [`MindatClientBuilder`](crate::MindatClientBuilder) is shared by every
+//! clone of the resulting client, so many tasks using the same client wait
+//! for a permit instead of exceeding the API's rate limits.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Rate limit configuration.
+///
+/// Combines a token bucket (requests per second with an optional burst) and
+/// a cap on the number of requests in flight at once. Either part may be
+/// used on its own.
+///
+/// # Example
+///
+/// ```no_run
+/// use mindat_rs::{MindatClient, RateLimit};
+///
+/// let client = MindatClient::builder()
+///     .token("your-token")
+///     .rate_limit(RateLimit::per_second(5.0).burst(10).max_concurrent(4))
+///     .build()?;
+/// # Ok::<(), mindat_rs::MindatError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    requests_per_second: Option<f64>,
+    burst: u32,
+    max_concurrent: Option<usize>,
+}
+
+impl RateLimit {
+    /// Allow at most `requests` requests per second.
+    pub fn per_second(requests: f64) -> Self {
+        Self {
+            requests_per_second: Some(requests),
+            burst: 1,
+            max_concurrent: None,
+        }
+    }
+
+    /// Only limit the number of requests in flight at once.
+    pub fn concurrency(max: usize) -> Self {
+        Self {
+            requests_per_second: None,
+            burst: 1,
+            max_concurrent: Some(max),
+        }
+    }
+
+    /// Allow up to `burst` requests to be made back to back before the
+    /// per-second rate applies. Defaults to `1`.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Limit the number of requests in flight at once.
+    ///
+    /// A request stops counting once its response has been received. For
+    /// pages streamed by an [`ItemStream`](crate::stream::ItemStream) that is
+    /// once the response headers have arrived, so the download of their body
+    /// is not limited.
+    pub fn max_concurrent(mut self, max: usize) -> Self {
+        self.max_concurrent = Some(max);
+        self
+    }
+}
+
+/// Token bucket state.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Take a token, returning how long the caller must wait before using it.
+    ///
+    /// Tokens may go negative: each caller reserves its slot immediately,
+    /// which keeps waiting callers in arrival order.
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+        self.tokens -= 1.0;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// Give back a token that was reserved but not used.
+    fn release(&mut self) {
+        self.tokens = (self.tokens + 1.0).min(self.capacity);
+    }
+}
+
+/// A token reserved from a bucket, given back if dropped before it is used.
+struct Reservation<'a> {
+    bucket: &'a Mutex<Bucket>,
+    used: bool,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if self.used {
+            return;
+        }
+        // A poisoned bucket has no state worth restoring
+        if let Ok(mut bucket) = self.bucket.lock() {
+            bucket.release();
+        }
+    }
+}
+
+/// Shared limiter enforcing a [`RateLimit`].
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    bucket: Option<Mutex<Bucket>>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+/// Permission to send one request. Releases the concurrency slot on drop.
+#[derive(Debug)]
+pub(crate) struct RatePermit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    /// Create a limiter from its configuration.
+    pub(crate) fn new(limit: &RateLimit) -> Self {
+        let bucket = limit
+            .requests_per_second
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| {
+                Mutex::new(Bucket {
+                    rate,
+                    capacity: f64::from(limit.burst),
+                    tokens: f64::from(limit.burst),
+                    updated: Instant::now(),
+                })
+            });
+        let in_flight = limit
+            .max_concurrent
+            .map(|max| Arc::new(Semaphore::new(max.max(1))));
+
+        Self { bucket, in_flight }
+    }
+
+    /// Wait until a request may be sent.
+    ///
+    /// Dropping the returned future before it completes gives back the token
+    /// it reserved, so cancelled requests do not use up the rate.
+    pub(crate) async fn acquire(&self) -> RatePermit {
+        let slot = match &self.in_flight {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("rate limiter semaphore closed"),
+            ),
+            None => None,
+        };
+
+        if let Some(bucket) = &self.bucket {
+            let wait = bucket.lock().expect("rate limiter lock poisoned").reserve();
+            let mut reservation = Reservation {
+                bucket,
+                used: false,
+            };
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+            reservation.used = true;
+        }
+
+        RatePermit { _slot: slot }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bucket_spaces_out_requests() {
+        let limiter = RateLimiter::new(&RateLimit::per_second(10.0).burst(2));
+        let start = Instant::now();
+
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+
+        // Two requests use the burst, the other two wait 100ms each
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancelled_acquire_returns_its_token() {
+        let limiter = RateLimiter::new(&RateLimit::per_second(10.0));
+        let start = Instant::now();
+
+        limiter.acquire().await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), limiter.acquire())
+                .await
+                .is_err()
+        );
+        limiter.acquire().await;
+
+        // The cancelled request does not push the next one back another 100ms
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_slots_are_released() {
+        let limiter = RateLimiter::new(&RateLimit::concurrency(1));
+
+        let first = limiter.acquire().await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), limiter.acquire())
+                .await
+                .is_err()
+        );
+        drop(first);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), limiter.acquire())
+                .await
+                .is_ok()
+        );
+    }
+}
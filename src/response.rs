@@ -0,0 +1,108 @@
+//! Responses with their HTTP metadata.
+//!
+//! The `*_with_meta` methods of [`MindatClient`](crate::MindatClient) return a
+//! [`Response`], which holds the decoded body together with the status,
+//! headers and final URL of the response, how long the request took and how
+//! many attempts it needed.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn example() -> mindat_rs::Result<()> {
+//! use mindat_rs::MindatClient;
+//!
+//! let client = MindatClient::new("your-token");
+//! let response = client.geomaterial_with_meta(3337).await?;
+//! println!(
+//!     "{:?} in {:?} after {} attempt(s), request ID {:?}",
+//!     response.body.name,
+//!     response.elapsed,
+//!     response.attempts,
+//!     response.request_id(),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+use url::Url;
+
+use crate::decode::SkippedRecord;
+use crate::retry;
+
+/// A decoded response body with the metadata of the response.
+#[derive(Debug, Clone)]
+pub struct Response<T> {
+    /// The decoded body.
+    pub body: T,
+    /// HTTP status code.
+    pub status: StatusCode,
+    /// Response headers.
+    pub headers: HeaderMap,
+    /// Final URL of the response, after any redirects.
+    pub url: Url,
+    /// Time from the start of the request until the response was decoded,
+    /// including retries and waiting for the rate limiter.
+    pub elapsed: Duration,
+    /// Number of attempts made; 0 when the response came from a cache.
+    pub attempts: u32,
+    /// Whether the response was served from the in-memory or disk cache
+    /// without contacting the API.
+    pub cached: bool,
+    /// Results of a page that could not be decoded, in
+    /// [lenient](crate::decode::DecodeMode::Lenient) mode.
+    pub skipped: Vec<SkippedRecord>,
+}
+
+impl<T> Response<T> {
+    /// The value of a header, if it is present and valid text.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    /// Request ID assigned by the server (`X-Request-Id`, or Cloudflare's
+    /// `CF-Ray`).
+    pub fn request_id(&self) -> Option<&str> {
+        self.header("x-request-id")
+            .or_else(|| self.header("cf-ray"))
+    }
+
+    /// Number of requests left in the current rate limit window, from the
+    /// `X-RateLimit-Remaining` header.
+    pub fn rate_limit_remaining(&self) -> Option<u64> {
+        self.header("x-ratelimit-remaining")?.trim().parse().ok()
+    }
+
+    /// Delay requested by the `Retry-After` header.
+    pub fn retry_after(&self) -> Option<Duration> {
+        retry::retry_after(&self.headers)
+    }
+
+    /// Cache status reported by a CDN in front of the API, from the
+    /// `CF-Cache-Status` or `X-Cache` header.
+    pub fn cdn_cache_status(&self) -> Option<&str> {
+        self.header("cf-cache-status")
+            .or_else(|| self.header("x-cache"))
+    }
+
+    /// Discard the metadata.
+    pub fn into_body(self) -> T {
+        self.body
+    }
+
+    /// Transform the body, keeping the metadata.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Response<U> {
+        Response {
+            body: f(self.body),
+            status: self.status,
+            headers: self.headers,
+            url: self.url,
+            elapsed: self.elapsed,
+            attempts: self.attempts,
+            cached: self.cached,
+            skipped: self.skipped,
+        }
+    }
+}
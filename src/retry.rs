@@ -0,0 +1,262 @@
+//! Retry policy for failed requests.
+//!
+//! By default the client makes a single attempt per request. Configure a
+//! [`RetryPolicy`] on the [`MindatClientBuilder`](crate::MindatClientBuilder)
+//! to retry rate-limited, failing or unreachable requests with exponential
+//! backoff.
+
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::error::MindatError;
+
+/// Kinds of transport errors that can be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryableErrorKind {
+    /// The request timed out.
+    Timeout,
+    /// The connection could not be established.
+    Connect,
+    /// The request could not be sent.
+    Request,
+    /// The response body could not be read.
+    Body,
+    /// A custom [`Transport`](crate::transport::Transport) failed, see
+    /// [`MindatError::Transport`].
+    Transport,
+}
+
+impl RetryableErrorKind {
+    /// Returns true if `err` is of this kind.
+    fn matches(self, err: &MindatError) -> bool {
+        match (self, err) {
+            (Self::Timeout, MindatError::Request(e)) => e.is_timeout(),
+            (Self::Connect, MindatError::Request(e)) => e.is_connect(),
+            (Self::Request, MindatError::Request(e)) => e.is_request(),
+            (Self::Body, MindatError::Request(e)) => e.is_body(),
+            (Self::Transport, MindatError::Transport(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Configuration for retrying failed requests.
+///
+/// Delays grow exponentially from `base_delay` up to `max_delay`. For 429 and
+/// 503 responses carrying a `Retry-After` header the server-provided delay is
+/// used instead; if it exceeds `max_delay` the request is not retried.
+///
+/// # Example
+///
+/// ```no_run
+/// use mindat_rs::{MindatClient, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let client = MindatClient::builder()
+///     .token("your-token")
+///     .retry_policy(
+///         RetryPolicy::new()
+///             .max_attempts(5)
+///             .base_delay(Duration::from_millis(250))
+///             .max_delay(Duration::from_secs(10)),
+///     )
+///     .build()?;
+/// # Ok::<(), mindat_rs::MindatError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    retry_statuses: Vec<u16>,
+    retry_errors: Vec<RetryableErrorKind>,
+}
+
+impl RetryPolicy {
+    /// Create a policy with sensible defaults.
+    ///
+    /// Makes up to 3 attempts, starting at 500ms and backing off to at most
+    /// 30s, retrying 429/500/502/503/504 responses as well as timeouts,
+    /// connection failures and errors of custom transports.
+    pub fn new() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            retry_statuses: vec![429, 500, 502, 503, 504],
+            retry_errors: vec![
+                RetryableErrorKind::Timeout,
+                RetryableErrorKind::Connect,
+                RetryableErrorKind::Transport,
+            ],
+        }
+    }
+
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::new().max_attempts(1)
+    }
+
+    /// Set the total number of attempts, including the first one.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set the maximum delay between attempts.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set the fraction (0.0 to 1.0) of each backoff delay that is randomized.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set which HTTP status codes are retried.
+    pub fn retry_on_status(mut self, statuses: Vec<u16>) -> Self {
+        self.retry_statuses = statuses;
+        self
+    }
+
+    /// Set which kinds of transport errors are retried.
+    pub fn retry_on_errors(mut self, kinds: Vec<RetryableErrorKind>) -> Self {
+        self.retry_errors = kinds;
+        self
+    }
+
+    /// Get the total number of attempts.
+    pub fn attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Delay before retrying a failed request, or `None` if it should not be
+    /// retried.
+    pub(crate) fn retry_error_delay(&self, err: &MindatError, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.retry_errors.iter().any(|k| k.matches(err)) {
+            return None;
+        }
+        Some(self.backoff(attempt))
+    }
+
+    /// Delay before retrying a response, or `None` if it should not be
+    /// retried.
+    pub(crate) fn retry_status_delay(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        attempt: u32,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.retry_statuses.contains(&status.as_u16()) {
+            return None;
+        }
+        if matches!(status.as_u16(), 429 | 503) {
+            if let Some(delay) = retry_after(headers) {
+                return (delay <= self.max_delay).then_some(delay);
+            }
+        }
+        Some(self.backoff(attempt))
+    }
+
+    /// Exponential backoff delay after the given (1-based) attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+        if self.jitter == 0.0 {
+            return delay;
+        }
+        delay.mul_f64(1.0 - self.jitter * random_fraction())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse a `Retry-After` header given either in seconds or as an HTTP date.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// A pseudo-random number in `[0, 1)`, good enough for jitter.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy::new()
+            .max_attempts(10)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(1000))
+            .jitter(0.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(5), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_retry_after_seconds() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn test_retry_after_past_date() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_retry_after_beyond_max_delay_is_not_retried() {
+        let policy = RetryPolicy::new().max_delay(Duration::from_secs(5));
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("60"));
+
+        assert_eq!(
+            policy.retry_status_delay(StatusCode::TOO_MANY_REQUESTS, &headers, 1),
+            None
+        );
+        assert_eq!(
+            policy.retry_status_delay(StatusCode::NOT_FOUND, &HeaderMap::new(), 1),
+            None
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Incremental splitting of a page of results.
+//!
+//! A [`PageSplitter`] is fed the body of a paginated response chunk by chunk
+//! and hands out the raw JSON of each element of the `results` array as soon
+//! as the element is complete, so a page never has to be held in memory as a
+//! whole. The other top-level fields, such as `count` and `next`, are parsed
+//! and kept.
+
+use serde::de::Error as _;
+use serde_json::{Map, Value};
+
+/// Where the splitter is in the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the opening brace of the page.
+    Start,
+    /// Before a field name or the closing brace of the page.
+    Key,
+    /// After a field name, before its colon.
+    Colon,
+    /// Before the value of a field.
+    Value,
+    /// Inside the `results` array, before an element or the closing bracket.
+    Results,
+    /// After the closing brace of the page.
+    End,
+}
+
+/// Progress through a JSON value that may span several chunks.
+#[derive(Debug, Default)]
+struct Scan {
+    /// Bytes of the value scanned so far.
+    len: usize,
+    /// Number of unclosed objects and arrays.
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Scan {
+    /// Continue scanning the value at the start of `buf`, returning its
+    /// length once it is complete.
+    fn advance(&mut self, buf: &[u8]) -> Option<usize> {
+        while let Some(&byte) = buf.get(self.len) {
+            self.len += 1;
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    if self.depth == 0 {
+                        return Some(self.len);
+                    }
+                }
+                continue;
+            }
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' if self.depth > 0 => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        return Some(self.len);
+                    }
+                }
+                // The end of a number, boolean or null
+                b'}' | b']' | b',' | b' ' | b'\t' | b'\n' | b'\r' if self.depth == 0 => {
+                    return Some(self.len - 1);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Splits a page of results into its elements.
+#[derive(Debug)]
+pub(crate) struct PageSplitter {
+    buf: Vec<u8>,
+    pos: usize,
+    state: State,
+    scan: Scan,
+    key: String,
+    fields: Map<String, Value>,
+}
+
+impl PageSplitter {
+    pub(crate) fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            pos: 0,
+            state: State::Start,
+            scan: Scan::default(),
+            key: String::new(),
+            fields: Map::new(),
+        }
+    }
+
+    /// Append the next chunk of the body.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) {
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// The next complete element of `results`, or `None` if more of the body
+    /// is needed.
+    pub(crate) fn next_item(&mut self) -> serde_json::Result<Option<Vec<u8>>> {
+        loop {
+            self.skip_separators();
+            let Some(&byte) = self.buf.get(self.pos) else {
+                return Ok(None);
+            };
+            match self.state {
+                State::Start => {
+                    self.expect(byte, b'{', "expected a JSON object")?;
+                    self.state = State::Key;
+                }
+                State::Key if byte == b'}' => {
+                    self.pos += 1;
+                    self.state = State::End;
+                }
+                State::Key => {
+                    if byte != b'"' {
+                        return Err(serde_json::Error::custom("expected a field name"));
+                    }
+                    let Some(key) = self.take_value() else {
+                        return Ok(None);
+                    };
+                    self.key = serde_json::from_slice(key)?;
+                    self.state = State::Colon;
+                }
+                State::Colon => {
+                    self.expect(byte, b':', "expected `:` after a field name")?;
+                    self.state = State::Value;
+                }
+                State::Value if self.key == "results" => {
+                    self.expect(byte, b'[', "expected `results` to be an array")?;
+                    self.state = State::Results;
+                }
+                State::Value => {
+                    let Some(value) = self.take_value() else {
+                        return Ok(None);
+                    };
+                    let value = serde_json::from_slice(value)?;
+                    self.fields.insert(std::mem::take(&mut self.key), value);
+                    self.state = State::Key;
+                }
+                State::Results if byte == b']' => {
+                    self.pos += 1;
+                    self.state = State::Key;
+                }
+                State::Results => {
+                    return Ok(self.take_value().map(<[u8]>::to_vec));
+                }
+                State::End => {
+                    return Err(serde_json::Error::custom(
+                        "trailing characters after the page",
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Check that the whole page has been read.
+    pub(crate) fn finish(&self) -> serde_json::Result<()> {
+        if self.state == State::End {
+            Ok(())
+        } else {
+            Err(serde_json::Error::custom(
+                "response ended before the end of the page",
+            ))
+        }
+    }
+
+    /// A top-level field of the page other than `results`, once it has been
+    /// read.
+    pub(crate) fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.get(name)
+    }
+
+    /// The part of the body that has not been consumed yet.
+    pub(crate) fn remaining(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Skip whitespace, and the commas between fields and elements.
+    fn skip_separators(&mut self) {
+        let commas = matches!(self.state, State::Key | State::Results);
+        while let Some(&byte) = self.buf.get(self.pos) {
+            if byte.is_ascii_whitespace() || (commas && byte == b',') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Consume `byte` if it is `expected`.
+    fn expect(&mut self, byte: u8, expected: u8, message: &str) -> serde_json::Result<()> {
+        if byte != expected {
+            return Err(serde_json::Error::custom(message));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Consume the value at the current position once it is complete.
+    fn take_value(&mut self) -> Option<&[u8]> {
+        let len = self.scan.advance(&self.buf[self.pos..])?;
+        self.scan = Scan::default();
+        let start = self.pos;
+        self.pos += len;
+        Some(&self.buf[start..self.pos])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"{
+        "count": 3,
+        "next": "https://api.mindat.org/v1/geomaterials/?page=2",
+        "results": [
+            {"id": 1, "name": "Quartz {\"SiO2\"}", "tags": [1, [2]]},
+            {"id": 2, "name": "Calcite ]"},
+            7
+        ],
+        "previous": null
+    }"#;
+
+    /// Split `body` fed in chunks of `size` bytes.
+    fn split(body: &str, size: usize) -> (Vec<Value>, PageSplitter) {
+        let mut splitter = PageSplitter::new();
+        let mut items = Vec::new();
+        for chunk in body.as_bytes().chunks(size) {
+            splitter.feed(chunk);
+            while let Some(item) = splitter.next_item().unwrap() {
+                items.push(serde_json::from_slice(&item).unwrap());
+            }
+        }
+        (items, splitter)
+    }
+
+    #[test]
+    fn test_split_at_every_chunk_size() {
+        let expected: Value = serde_json::from_str(PAGE).unwrap();
+        for size in 1..=PAGE.len() {
+            let (items, splitter) = split(PAGE, size);
+            assert_eq!(Value::from(items), expected["results"], "chunk size {size}");
+            splitter.finish().unwrap();
+            assert_eq!(splitter.field("count"), Some(&Value::from(3)));
+            assert_eq!(splitter.field("next"), Some(&expected["next"]));
+            assert_eq!(splitter.field("previous"), Some(&Value::Null));
+        }
+    }
+
+    #[test]
+    fn test_truncated_page() {
+        let (items, splitter) = split(&PAGE[..PAGE.find("Calcite").unwrap()], 16);
+        assert_eq!(items.len(), 1);
+        assert!(splitter.finish().is_err());
+    }
+
+    #[test]
+    fn test_rejects_non_object() {
+        let mut splitter = PageSplitter::new();
+        splitter.feed(b"[1, 2]");
+        assert!(splitter.next_item().is_err());
+    }
+}
@@ -0,0 +1,679 @@
+//! Auto-paginating streams over list endpoints.
+//!
+//! A [`PageStream`] requests the first page of a list endpoint, yields its
+//! results one at a time and keeps following the `next` link returned by the
+//! API until there are no more pages (or a configured limit is reached).
+//!
+//! An [`ItemStream`] does the same without ever holding a whole page: each
+//! element of a page's `results` array is decoded and yielded as soon as it
+//! has been received.
+
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use url::Url;
+
+use crate::client::MindatClient;
+use crate::decode::{self, DecodeMode, SkippedRecord};
+use crate::error::{self, MindatError, Result};
+use crate::models::{CursorPaginatedResponse, PaginatedResponse};
+use crate::split::PageSplitter;
+use crate::transport::{BodyStream, StreamingResponse};
+
+/// Default number of pages fetched ahead of the consumer.
+const DEFAULT_PREFETCH: usize = 1;
+
+/// A single page fetched by a [`PageStream`].
+pub(crate) struct Page<T> {
+    /// URL the page was fetched from.
+    pub(crate) url: Url,
+    /// Resolved URL of the following page, if any.
+    pub(crate) next: Option<Url>,
+    /// Results contained in the page.
+    pub(crate) items: Vec<T>,
+}
+
+/// Response types that can be followed page by page.
+pub(crate) trait Paginated<T> {
+    /// Split the response into its `next` link and its results.
+    fn into_page_parts(self) -> (Option<String>, Vec<T>);
+
+    /// Number of results in the page.
+    fn result_count(&self) -> usize;
+}
+
+impl<T> Paginated<T> for PaginatedResponse<T> {
+    fn into_page_parts(self) -> (Option<String>, Vec<T>) {
+        (self.next, self.results)
+    }
+
+    fn result_count(&self) -> usize {
+        self.results.len()
+    }
+}
+
+impl<T> Paginated<T> for CursorPaginatedResponse<T> {
+    fn into_page_parts(self) -> (Option<String>, Vec<T>) {
+        (self.next, self.results)
+    }
+
+    fn result_count(&self) -> usize {
+        self.results.len()
+    }
+}
+
+type FetchFn<T> = fn(MindatClient, Url) -> BoxFuture<'static, Result<Page<T>>>;
+
+/// Aborts the prefetch task when the stream is dropped.
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A stream of results that transparently follows paginated responses.
+///
+/// Created by methods such as [`MindatClient::geomaterials_stream`]. Nothing
+/// is requested until the stream is first polled. Limits and prefetching
+/// should be configured before polling begins.
+///
+/// Both page-number and cursor pagination are supported: the stream simply
+/// follows whichever `next` link the API returns. The position reached can be
+/// read back with [`resume_cursor`](Self::resume_cursor) or
+/// [`resume_page`](Self::resume_page) to continue an interrupted iteration
+/// later.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> mindat_rs::Result<()> {
+/// use futures::StreamExt;
+/// use mindat_rs::{GeomaterialsQuery, MindatClient};
+///
+/// let client = MindatClient::new("your-token");
+/// let mut minerals = client
+///     .geomaterials_stream(GeomaterialsQuery::new().with_elements("Cu"))
+///     .max_items(500);
+///
+/// while let Some(mineral) = minerals.next().await {
+///     let mineral = mineral?;
+///     println!("{}: {:?}", mineral.id, mineral.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct PageStream<T> {
+    client: MindatClient,
+    start: Option<Result<Url>>,
+    fetch: FetchFn<T>,
+    max_items: Option<usize>,
+    max_pages: Option<usize>,
+    prefetch: usize,
+    pages: Option<BoxStream<'static, Result<Page<T>>>>,
+    prefetch_task: Option<AbortOnDrop>,
+    buffer: VecDeque<T>,
+    start_url: Option<Url>,
+    current_url: Option<Url>,
+    next_url: Option<Url>,
+    pages_fetched: usize,
+    yielded: usize,
+    done: bool,
+}
+
+// The stream never pins its items in place, so it is always `Unpin`.
+impl<T> Unpin for PageStream<T> {}
+
+impl<T: Send + 'static> PageStream<T> {
+    /// Create a stream starting at `start`, decoding each page as `R`.
+    pub(crate) fn new<R>(client: MindatClient, start: Result<Url>) -> Self
+    where
+        R: DeserializeOwned + Paginated<T> + Send + 'static,
+    {
+        Self {
+            client,
+            start_url: start.as_ref().ok().cloned(),
+            start: Some(start),
+            fetch: fetch_page::<T, R>,
+            max_items: None,
+            max_pages: None,
+            prefetch: DEFAULT_PREFETCH,
+            pages: None,
+            prefetch_task: None,
+            buffer: VecDeque::new(),
+            current_url: None,
+            next_url: None,
+            pages_fetched: 0,
+            yielded: 0,
+            done: false,
+        }
+    }
+
+    /// Stop after yielding at most `max` results.
+    pub fn max_items(mut self, max: usize) -> Self {
+        self.max_items = Some(max);
+        self
+    }
+
+    /// Stop after fetching at most `max` pages.
+    pub fn max_pages(mut self, max: usize) -> Self {
+        self.max_pages = Some(max);
+        self
+    }
+
+    /// Set how many pages may be fetched ahead of the consumer.
+    ///
+    /// While the current page is being consumed, up to `pages` further pages
+    /// are requested in a background task. Use `0` to only fetch a page once
+    /// the previous one has been fully consumed. Defaults to `1`.
+    ///
+    /// Pages are never fetched beyond those needed for
+    /// [`max_items`](Self::max_items). The background task needs a Tokio
+    /// runtime; when the stream is first polled outside one, pages are
+    /// fetched without prefetching.
+    pub fn prefetch(mut self, pages: usize) -> Self {
+        self.prefetch = pages;
+        self
+    }
+
+    /// Number of results yielded so far.
+    pub fn items_yielded(&self) -> usize {
+        self.yielded
+    }
+
+    /// Number of pages fetched and handed to the consumer so far.
+    pub fn pages_fetched(&self) -> usize {
+        self.pages_fetched
+    }
+
+    /// URL of the page currently being consumed, if any has been fetched.
+    pub fn current_url(&self) -> Option<&Url> {
+        self.current_url.as_ref()
+    }
+
+    /// URL of the page an interrupted iteration should resume from.
+    ///
+    /// This is the page currently being consumed, or the following page once
+    /// every result of the current page has been yielded. Returns `None` once
+    /// the last page has been fully consumed.
+    pub fn resume_url(&self) -> Option<&Url> {
+        match &self.current_url {
+            None => self.start_url.as_ref(),
+            Some(current) if !self.buffer.is_empty() => Some(current),
+            Some(_) => self.next_url.as_ref(),
+        }
+    }
+
+    /// Cursor to resume an interrupted iteration from.
+    ///
+    /// Pass it back with [`LocalitiesQuery::cursor`](crate::LocalitiesQuery::cursor)
+    /// to continue where this stream stopped. Results of a partially consumed
+    /// page are yielded again when resuming. Returns `None` when the API is
+    /// using page numbers or there is nothing left to fetch.
+    pub fn resume_cursor(&self) -> Option<String> {
+        self.resume_url().and_then(|url| query_value(url, "cursor"))
+    }
+
+    /// Page number to resume an interrupted iteration from.
+    ///
+    /// The page-number counterpart of [`resume_cursor`](Self::resume_cursor).
+    pub fn resume_page(&self) -> Option<i32> {
+        self.resume_url()
+            .and_then(|url| query_value(url, "page"))
+            .and_then(|page| page.parse().ok())
+    }
+
+    /// Build the underlying page stream on first poll.
+    fn start_pages(&mut self) -> BoxStream<'static, Result<Page<T>>> {
+        let start = self
+            .start
+            .take()
+            .expect("page stream started more than once");
+        let client = self.client.clone();
+        let fetch = self.fetch;
+        let max_items = self.max_items;
+
+        // Counts the results fetched so far, so no page is requested once
+        // there are enough to reach `max_items`
+        let pages = stream::unfold((Some(start), 0), move |(next, fetched)| {
+            let client = client.clone();
+            async move {
+                let url = match next? {
+                    Ok(url) => url,
+                    Err(e) => return Some((Err(e), (None, fetched))),
+                };
+                match fetch(client, url).await {
+                    Ok(page) => {
+                        let fetched = fetched + page.items.len();
+                        let next = match max_items {
+                            Some(max) if fetched >= max => None,
+                            _ => page.next.clone().map(Ok),
+                        };
+                        Some((Ok(page), (next, fetched)))
+                    }
+                    Err(e) => Some((Err(e), (None, fetched))),
+                }
+            }
+        });
+        let pages = match self.max_pages {
+            Some(max) => pages.take(max).boxed(),
+            None => pages.boxed(),
+        };
+
+        let runtime = match tokio::runtime::Handle::try_current() {
+            Ok(runtime) if self.prefetch > 0 => runtime,
+            _ => return pages,
+        };
+
+        // A page holds a permit from before it is requested until the
+        // consumer takes it, so at most `prefetch` pages are requested or
+        // waiting ahead of the page being consumed.
+        let permits = Arc::new(Semaphore::new(self.prefetch));
+        let (tx, mut rx) = tokio::sync::mpsc::channel(self.prefetch);
+        let task = runtime.spawn(async move {
+            let mut pages = pages;
+            loop {
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    break;
+                };
+                let Some(page) = pages.next().await else {
+                    break;
+                };
+                if tx.send((page, permit)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        self.prefetch_task = Some(AbortOnDrop(task));
+        stream::poll_fn(move |cx| {
+            rx.poll_recv(cx)
+                .map(|next| next.map(|(page, _permit)| page))
+        })
+        .boxed()
+    }
+
+    /// Stop the stream and release any background work.
+    fn finish(&mut self) {
+        self.done = true;
+        self.pages = None;
+        self.prefetch_task = None;
+    }
+}
+
+impl<T: Send + 'static> Stream for PageStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            if this.max_items.is_some_and(|max| this.yielded >= max) {
+                this.finish();
+                return Poll::Ready(None);
+            }
+            if let Some(item) = this.buffer.pop_front() {
+                this.yielded += 1;
+                return Poll::Ready(Some(Ok(item)));
+            }
+
+            if this.pages.is_none() {
+                this.pages = Some(this.start_pages());
+            }
+            let pages = this.pages.as_mut().expect("page stream not started");
+
+            match pages.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(page))) => {
+                    this.pages_fetched += 1;
+                    this.current_url = Some(page.url);
+                    this.next_url = page.next;
+                    this.buffer.extend(page.items);
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    this.finish();
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(None) => {
+                    this.finish();
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
+}
+
+/// A stream of results decoded while each page is still being received.
+///
+/// Created by methods such as [`MindatClient::geomaterials_items`]. Like a
+/// [`PageStream`] it follows the `next` links returned by the API, but
+/// instead of decoding a page in one go it yields every element of the
+/// page's `results` array as soon as it has been received, so memory use is
+/// bounded by the largest single result rather than by the page size. Pages
+/// are fetched one after the other, without prefetching.
+///
+/// Streamed pages are not served from or stored in the in-memory cache, and
+/// are not coalesced with identical requests. With a disk cache configured,
+/// pages are read in full so they can be served from or stored in it.
+/// In [lenient](DecodeMode::Lenient) mode results that fail to decode are
+/// skipped and listed by [`skipped`](Self::skipped).
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> mindat_rs::Result<()> {
+/// use futures::StreamExt;
+/// use mindat_rs::{GeomaterialsQuery, MindatClient};
+///
+/// let client = MindatClient::new("your-token");
+/// let mut minerals = client.geomaterials_items(GeomaterialsQuery::new().page_size(1000));
+///
+/// while let Some(mineral) = minerals.next().await {
+///     let mineral = mineral?;
+///     println!("{}: {:?}", mineral.id, mineral.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ItemStream<T> {
+    client: MindatClient,
+    next: Option<Result<Url>>,
+    max_items: Option<usize>,
+    max_pages: Option<usize>,
+    opening: Option<BoxFuture<'static, Result<StreamingResponse>>>,
+    body: Option<BodyStream>,
+    splitter: PageSplitter,
+    current_url: Option<Url>,
+    index: usize,
+    count: Option<u64>,
+    skipped: Vec<SkippedRecord>,
+    yielded: usize,
+    pages_fetched: usize,
+    done: bool,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> ItemStream<T> {
+    pub(crate) fn new(client: MindatClient, start: Result<Url>) -> Self {
+        Self {
+            client,
+            next: Some(start),
+            max_items: None,
+            max_pages: None,
+            opening: None,
+            body: None,
+            splitter: PageSplitter::new(),
+            current_url: None,
+            index: 0,
+            count: None,
+            skipped: Vec::new(),
+            yielded: 0,
+            pages_fetched: 0,
+            done: false,
+            _item: PhantomData,
+        }
+    }
+
+    /// Stop after yielding at most `max` results.
+    pub fn max_items(mut self, max: usize) -> Self {
+        self.max_items = Some(max);
+        self
+    }
+
+    /// Stop after fetching at most `max` pages.
+    pub fn max_pages(mut self, max: usize) -> Self {
+        self.max_pages = Some(max);
+        self
+    }
+
+    /// Number of results yielded so far.
+    pub fn items_yielded(&self) -> usize {
+        self.yielded
+    }
+
+    /// Number of pages requested so far.
+    pub fn pages_fetched(&self) -> usize {
+        self.pages_fetched
+    }
+
+    /// URL of the page currently being read, if any has been requested.
+    pub fn current_url(&self) -> Option<&Url> {
+        self.current_url.as_ref()
+    }
+
+    /// Results skipped so far in [lenient](DecodeMode::Lenient) mode.
+    pub fn skipped(&self) -> &[SkippedRecord] {
+        &self.skipped
+    }
+
+    /// Total number of results reported by the first page, once its `count`
+    /// field has been read.
+    pub fn total_count(&self) -> Option<u64> {
+        self.count
+    }
+
+    /// Decode the next element of the page being read, if one is complete.
+    ///
+    /// In lenient mode elements that fail to decode are skipped.
+    fn next_item(&mut self) -> Result<Option<T>> {
+        let mode = self.client.decode_mode();
+        let url = self.current_url.as_ref().map_or("", Url::as_str);
+        loop {
+            let raw = match self.splitter.next_item() {
+                Ok(Some(raw)) => raw,
+                Ok(None) => {
+                    self.record_count();
+                    return Ok(None);
+                }
+                Err(source) => {
+                    return Err(MindatError::Deserialization {
+                        url: url.to_string(),
+                        path: ".".to_string(),
+                        snippet: error::snippet(self.splitter.remaining()),
+                        source,
+                    });
+                }
+            };
+
+            let index = self.index;
+            self.index += 1;
+            if mode == DecodeMode::Lenient {
+                let result = match serde_json::from_slice(&raw) {
+                    Ok(value) => decode::record(index, value),
+                    Err(e) => Err(SkippedRecord {
+                        index,
+                        id: None,
+                        path: decode::record_path(index, "."),
+                        message: e.to_string(),
+                    }),
+                };
+                match result {
+                    Ok(item) => return Ok(Some(item)),
+                    Err(skipped) => self.skipped.push(skipped),
+                }
+                continue;
+            }
+
+            return decode::from_slice(mode, &raw)
+                .map(|(item, _)| Some(item))
+                .map_err(|e| MindatError::Deserialization {
+                    url: url.to_string(),
+                    path: decode::record_path(index, &e.path),
+                    snippet: error::snippet(&raw),
+                    source: e.source,
+                });
+        }
+    }
+
+    /// Remember the total reported by the first page.
+    fn record_count(&mut self) {
+        if self.pages_fetched == 1 && self.count.is_none() {
+            self.count = self
+                .splitter
+                .field("count")
+                .and_then(|count| count.as_u64());
+        }
+    }
+
+    /// Check that the page just read was complete and find the next one.
+    fn end_page(&mut self) -> Result<()> {
+        let url = self.current_url.take().expect("no page being read");
+        self.record_count();
+        if let Err(source) = self.splitter.finish() {
+            return Err(MindatError::Deserialization {
+                url: url.to_string(),
+                path: ".".to_string(),
+                snippet: error::snippet(self.splitter.remaining()),
+                source,
+            });
+        }
+        let next = self.splitter.field("next").and_then(|next| next.as_str());
+        self.next = match next {
+            Some(next) => resolve_next(&url, next).transpose(),
+            None => None,
+        };
+        self.splitter = PageSplitter::new();
+        Ok(())
+    }
+
+    /// Stop the stream and drop any response being read.
+    fn finish(&mut self) {
+        self.done = true;
+        self.opening = None;
+        self.body = None;
+    }
+}
+
+impl<T: DeserializeOwned> Stream for ItemStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            if this.max_items.is_some_and(|max| this.yielded >= max) {
+                this.finish();
+                return Poll::Ready(None);
+            }
+
+            if this.body.is_some() {
+                match this.next_item() {
+                    Ok(Some(item)) => {
+                        this.yielded += 1;
+                        return Poll::Ready(Some(Ok(item)));
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        this.finish();
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                let body = this.body.as_mut().expect("page body missing");
+                let result = match body.poll_next_unpin(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Some(Ok(chunk))) => {
+                        this.splitter.feed(&chunk);
+                        continue;
+                    }
+                    Poll::Ready(Some(Err(e))) => Err(e),
+                    Poll::Ready(None) => {
+                        this.body = None;
+                        this.end_page()
+                    }
+                };
+                if let Err(e) = result {
+                    this.finish();
+                    return Poll::Ready(Some(Err(e)));
+                }
+                continue;
+            }
+
+            if let Some(opening) = this.opening.as_mut() {
+                match opening.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(response)) => {
+                        this.opening = None;
+                        this.pages_fetched += 1;
+                        this.current_url = Some(response.url);
+                        this.body = Some(response.body);
+                        this.index = 0;
+                    }
+                    Poll::Ready(Err(e)) => {
+                        this.finish();
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                continue;
+            }
+
+            if this.max_pages.is_some_and(|max| this.pages_fetched >= max) {
+                this.finish();
+                return Poll::Ready(None);
+            }
+            match this.next.take() {
+                Some(Ok(url)) => {
+                    let client = this.client.clone();
+                    this.opening = Some(Box::pin(async move { client.open_url(url).await }));
+                }
+                Some(Err(e)) => {
+                    this.finish();
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => {
+                    this.finish();
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
+}
+
+/// Fetch one page from `url` and resolve its `next` link.
+fn fetch_page<T, R>(client: MindatClient, url: Url) -> BoxFuture<'static, Result<Page<T>>>
+where
+    T: Send + 'static,
+    R: DeserializeOwned + Paginated<T> + Send + 'static,
+{
+    Box::pin(async move {
+        let response: R = client.get_page_url(url.clone()).await?;
+        let (next, items) = response.into_page_parts();
+        let next = match next {
+            Some(next) => resolve_next(&url, &next)?,
+            None => None,
+        };
+        Ok(Page { url, next, items })
+    })
+}
+
+/// Resolve a `next` link against the URL of the current page.
+///
+/// Only the query string of the link is used, so pages are always requested
+/// from the client's own base URL even if the API reports a different host
+/// (for example behind a proxy or mirror). Returns `None` if the link points
+/// back at the current page.
+fn resolve_next(current: &Url, next: &str) -> Result<Option<Url>> {
+    let next = current.join(next)?;
+    let mut url = current.clone();
+    url.set_query(next.query());
+    Ok((url != *current).then_some(url))
+}
+
+/// Look up a query parameter in a URL.
+fn query_value(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+}
@@ -0,0 +1,261 @@
+//! Tracing spans and metrics for API requests.
+//!
+//! With the `tracing` feature every request made by the client runs inside a
+//! `mindat.request` span carrying the endpoint, the query string, the final
+//! status, the latency, the number of attempts and, for pages decoded as a
+//! whole, the number of results.
+//! With the `metrics` feature the same information is reported through the
+//! [`metrics`](https://docs.rs/metrics) facade:
+//!
+//! - `mindat_requests_total` (counter; `endpoint`, `status`, `cached` labels)
+//! - `mindat_request_duration_seconds` (histogram; `endpoint` label)
+//! - `mindat_request_retries_total` (counter; `endpoint` label)
+//! - `mindat_request_results` (histogram; `endpoint` label)
+//!
+//! Endpoints are reported with numeric path segments replaced by `{id}`, e.g.
+//! `geomaterials/{id}/`, to keep label cardinality low.
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub(crate) use enabled::Telemetry;
+
+#[cfg(not(any(feature = "tracing", feature = "metrics")))]
+pub(crate) use disabled::Telemetry;
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+mod enabled {
+    use std::time::Instant;
+    use url::Url;
+
+    use crate::error::Result;
+    use crate::transport::TransportResponse;
+
+    /// Measurements collected while a request is made.
+    pub(crate) struct Telemetry {
+        endpoint: String,
+        start: Instant,
+        status: Option<u16>,
+        attempts: u32,
+        cached: bool,
+        results: Option<usize>,
+        #[cfg(feature = "tracing")]
+        span: tracing::Span,
+    }
+
+    impl Telemetry {
+        /// Start measuring a request for `url`, whose endpoint path is `path`.
+        pub(crate) fn start(path: &str, url: &Url) -> Self {
+            let endpoint = super::endpoint_label(path);
+            #[cfg(feature = "tracing")]
+            let span = tracing::info_span!(
+                "mindat.request",
+                endpoint = %endpoint,
+                query = %super::redacted_query(url),
+                status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                attempts = tracing::field::Empty,
+                cached = tracing::field::Empty,
+                result_count = tracing::field::Empty,
+            );
+            #[cfg(not(feature = "tracing"))]
+            let _ = url;
+
+            Self {
+                endpoint,
+                start: Instant::now(),
+                status: None,
+                attempts: 0,
+                cached: false,
+                results: None,
+                #[cfg(feature = "tracing")]
+                span,
+            }
+        }
+
+        /// Span the request runs in.
+        #[cfg(feature = "tracing")]
+        pub(crate) fn span(&self) -> tracing::Span {
+            self.span.clone()
+        }
+
+        /// Record a response served from a cache.
+        pub(crate) fn cached(&mut self, response: &TransportResponse) {
+            self.cached = true;
+            self.response(response, 0);
+        }
+
+        /// Record the response the request ended with.
+        pub(crate) fn response(&mut self, response: &TransportResponse, attempts: u32) {
+            self.status = Some(response.status.as_u16());
+            self.attempts = attempts;
+        }
+
+        /// Record the number of results in the decoded page.
+        pub(crate) fn results(&mut self, count: usize) {
+            self.results = Some(count);
+        }
+
+        /// Record the status of a response whose body is streamed.
+        pub(crate) fn streamed(&mut self, status: reqwest::StatusCode, attempts: u32) {
+            self.status = Some(status.as_u16());
+            self.attempts = attempts;
+        }
+
+        /// Record a request that failed without a response.
+        pub(crate) fn failed(&mut self, attempts: u32) {
+            self.attempts = attempts;
+        }
+
+        /// Record a retry about to happen.
+        pub(crate) fn retry(&self, attempt: u32, delay: std::time::Duration) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                parent: &self.span,
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "retrying Mindat request"
+            );
+            #[cfg(not(feature = "tracing"))]
+            let _ = (attempt, delay);
+        }
+
+        /// Report the outcome of the request.
+        pub(crate) fn finish<T>(self, result: &Result<T>) {
+            let latency = self.start.elapsed();
+
+            #[cfg(feature = "tracing")]
+            {
+                let span = &self.span;
+                if let Some(status) = self.status {
+                    span.record("status", status);
+                }
+                span.record("latency_ms", latency.as_millis() as u64);
+                span.record("attempts", self.attempts);
+                span.record("cached", self.cached);
+                if let Some(results) = self.results {
+                    span.record("result_count", results as u64);
+                }
+                match result {
+                    Ok(_) => tracing::debug!(parent: span, "Mindat request completed"),
+                    Err(e) => tracing::warn!(parent: span, error = %e, "Mindat request failed"),
+                }
+            }
+
+            #[cfg(feature = "metrics")]
+            {
+                let status = match self.status {
+                    Some(status) => status.to_string(),
+                    None => "error".to_string(),
+                };
+                metrics::counter!(
+                    "mindat_requests_total",
+                    "endpoint" => self.endpoint.clone(),
+                    "status" => status,
+                    "cached" => self.cached.to_string(),
+                )
+                .increment(1);
+                metrics::histogram!(
+                    "mindat_request_duration_seconds",
+                    "endpoint" => self.endpoint.clone(),
+                )
+                .record(latency.as_secs_f64());
+                if self.attempts > 1 {
+                    metrics::counter!(
+                        "mindat_request_retries_total",
+                        "endpoint" => self.endpoint.clone(),
+                    )
+                    .increment(u64::from(self.attempts - 1));
+                }
+                if let Some(results) = self.results {
+                    metrics::histogram!(
+                        "mindat_request_results",
+                        "endpoint" => self.endpoint.clone(),
+                    )
+                    .record(results as f64);
+                }
+            }
+
+            #[cfg(not(feature = "tracing"))]
+            let _ = result;
+            #[cfg(not(feature = "metrics"))]
+            let _ = (latency, &self.endpoint);
+        }
+    }
+}
+
+#[cfg(not(any(feature = "tracing", feature = "metrics")))]
+mod disabled {
+    use url::Url;
+
+    use crate::error::Result;
+    use crate::transport::TransportResponse;
+
+    /// No-op stand-in used when neither `tracing` nor `metrics` is enabled.
+    pub(crate) struct Telemetry;
+
+    impl Telemetry {
+        pub(crate) fn start(_path: &str, _url: &Url) -> Self {
+            Self
+        }
+
+        pub(crate) fn cached(&mut self, _response: &TransportResponse) {}
+
+        pub(crate) fn response(&mut self, _response: &TransportResponse, _attempts: u32) {}
+
+        pub(crate) fn results(&mut self, _count: usize) {}
+
+        pub(crate) fn streamed(&mut self, _status: reqwest::StatusCode, _attempts: u32) {}
+
+        pub(crate) fn failed(&mut self, _attempts: u32) {}
+
+        pub(crate) fn retry(&self, _attempt: u32, _delay: std::time::Duration) {}
+
+        pub(crate) fn finish<T>(self, _result: &Result<T>) {}
+    }
+}
+
+/// Endpoint label for a path, with numeric segments replaced by `{id}`.
+#[cfg_attr(not(any(feature = "tracing", feature = "metrics")), allow(dead_code))]
+fn endpoint_label(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Query string of a URL without any credential-like parameters.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+fn redacted_query(url: &url::Url) -> String {
+    let pairs = url.query_pairs().filter(|(key, _)| {
+        let key = key.to_ascii_lowercase();
+        !key.contains("token") && !key.contains("key")
+    });
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_label_replaces_ids() {
+        assert_eq!(endpoint_label("geomaterials/3337/"), "geomaterials/{id}/");
+        assert_eq!(endpoint_label("locality-type/"), "locality-type/");
+        assert_eq!(endpoint_label("dana-8/12/"), "dana-8/{id}/");
+    }
+
+    #[test]
+    fn test_redacted_query_drops_credentials() {
+        let url =
+            url::Url::parse("https://api.mindat.org/v1/geomaterials/?name=quartz&token=secret")
+                .unwrap();
+        assert_eq!(redacted_query(&url), "name=quartz");
+    }
+}
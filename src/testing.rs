@@ -0,0 +1,865 @@
+//! An in-process fake of the Mindat API for tests.
+//!
+//! [`FakeMindat`] is a [`Transport`] that answers requests from fixture
+//! records instead of the network. It serves the list and detail endpoints of
+//! geomaterials, localities, IMA minerals, countries and locality metadata,
+//! plus fixed documents such as the classification and photo count
+//! endpoints, and behaves like the real API where tests are likely to notice:
+//!
+//! - Filters such as `name`, `q`, `ima`, `elements_inc`, `hardness_min`,
+//!   `id__in` and `crystal_system` are applied to the records, as are
+//!   `ordering`, `fields` and `omit`.
+//! - List endpoints are paginated by `page` and `page_size` with `count`,
+//!   `next` and `previous` links. Localities use `cursor` pagination unless a
+//!   `page` is requested. A page past the end is a 404, as on the real API.
+//! - When a token is configured, requests without a matching
+//!   `Authorization: Token ...` header get a 401, except for the IMA minerals
+//!   endpoint, which is public.
+//! - Unknown paths and IDs get a 404. Rate limiting can be simulated with
+//!   [`quota`](FakeMindat::quota) and any status can be injected with
+//!   [`fail_next`](FakeMindat::fail_next); both answer 429s with a
+//!   `Retry-After` of one second.
+//!
+//! Filter parameters the fake does not know are matched exactly against the
+//! record field of the same name; `updated_at`, `expand` and the optical
+//! range filters are ignored.
+//!
+//! # Fixtures
+//!
+//! Fixtures are JSON records in the shape the API returns. They can be added
+//! one collection at a time, or loaded from a file with
+//! [`FakeMindat::from_fixtures`]:
+//!
+//! ```json
+//! {
+//!   "geomaterials": [{"id": 3337, "name": "Quartz", "elements": ["O", "Si"]}],
+//!   "localities": [{"id": 3064, "txt": "Serra Pelada", "country": "Brazil"}],
+//!   "minerals_ima": [],
+//!   "locality_ages": [],
+//!   "locality_statuses": [],
+//!   "locality_types": [],
+//!   "geo_regions": [],
+//!   "countries": [],
+//!   "documents": {"dana-8/groups/": {"results": []}}
+//! }
+//! ```
+//!
+//! Every key is optional. [`FakeMindat::sample`] comes with a small set of
+//! common minerals and localities.
+//!
+//! # Example
+//!
+//! ```
+//! # async fn example() -> mindat_rs::Result<()> {
+//! use mindat_rs::GeomaterialsQuery;
+//! use mindat_rs::testing::FakeMindat;
+//!
+//! let fake = FakeMindat::sample().token("test-token");
+//! let client = fake.client_builder().build()?;
+//!
+//! let page = client
+//!     .geomaterials(GeomaterialsQuery::new().with_elements("Fe").page_size(1))
+//!     .await?;
+//! assert_eq!(page.count, Some(2));
+//! assert!(page.has_next());
+//! assert_eq!(fake.requests().len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::future::BoxFuture;
+use reqwest::StatusCode;
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue, RETRY_AFTER};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use crate::client::{DEFAULT_BASE_URL, MindatClient, MindatClientBuilder};
+use crate::error::{MindatError, Result};
+use crate::transport::{Transport, TransportRequest, TransportResponse};
+
+/// Page size used when a request does not set `page_size`.
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+/// Largest page size served, whatever the request asks for.
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Query parameters that never filter records.
+const IGNORED_PARAMS: &[&str] = &[
+    "page",
+    "page_size",
+    "cursor",
+    "fields",
+    "omit",
+    "expand",
+    "ordering",
+    "format",
+    "updated_at",
+    "non_utf",
+    "meteoritical_code_exists",
+    "size",
+];
+
+/// Fixture records served by a [`FakeMindat`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct Fixtures {
+    geomaterials: Vec<Value>,
+    localities: Vec<Value>,
+    minerals_ima: Vec<Value>,
+    locality_ages: Vec<Value>,
+    locality_statuses: Vec<Value>,
+    locality_types: Vec<Value>,
+    geo_regions: Vec<Value>,
+    countries: Vec<Value>,
+    /// Fixed responses by path relative to the base URL.
+    documents: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    requests: Vec<Url>,
+    failures: VecDeque<StatusCode>,
+}
+
+/// A fake Mindat API serving fixture records.
+///
+/// Clones share the request log and injected failures, so a test can keep one
+/// handle and give a clone to the client. See the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct FakeMindat {
+    base_url: Url,
+    token: Option<String>,
+    quota: Option<usize>,
+    fixtures: Arc<Fixtures>,
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeMindat {
+    /// Create a fake with no records that accepts any request.
+    pub fn new() -> Self {
+        Self::with_fixtures(Fixtures::default())
+    }
+
+    /// Create a fake with a small built-in set of minerals, localities,
+    /// locality metadata and classification documents.
+    pub fn sample() -> Self {
+        Self::from_json(include_str!("testing/sample.json")).expect("sample fixtures are valid")
+    }
+
+    /// Load fixtures from a JSON file.
+    pub fn from_fixtures(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_json(&contents).map_err(|e| match e {
+            MindatError::Config(message) => {
+                MindatError::Config(format!("{}: {}", path.display(), message))
+            }
+            e => e,
+        })
+    }
+
+    /// Load fixtures from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let fixtures = serde_json::from_str(json)
+            .map_err(|e| MindatError::Config(format!("invalid fixtures: {}", e)))?;
+        Ok(Self::with_fixtures(fixtures))
+    }
+
+    fn with_fixtures(fixtures: Fixtures) -> Self {
+        Self {
+            base_url: Url::parse(DEFAULT_BASE_URL).expect("default base URL is valid"),
+            token: None,
+            quota: None,
+            fixtures: Arc::new(fixtures),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Require `Authorization: Token <token>` on every request except those
+    /// to the IMA minerals endpoint.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Serve the API under a different base URL.
+    ///
+    /// Defaults to [`DEFAULT_BASE_URL`]. Requests outside of it get a 404.
+    pub fn base_url(mut self, url: &str) -> Result<Self> {
+        self.base_url = Url::parse(url)?;
+        Ok(self)
+    }
+
+    /// Answer every request after the first `requests` with a 429.
+    pub fn quota(mut self, requests: usize) -> Self {
+        self.quota = Some(requests);
+        self
+    }
+
+    /// Add geomaterial records.
+    pub fn with_geomaterials(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures)
+            .geomaterials
+            .extend(records);
+        self
+    }
+
+    /// Add locality records.
+    pub fn with_localities(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures).localities.extend(records);
+        self
+    }
+
+    /// Add IMA mineral records.
+    pub fn with_minerals_ima(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures)
+            .minerals_ima
+            .extend(records);
+        self
+    }
+
+    /// Add locality age records, identified by `age_id`.
+    pub fn with_locality_ages(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures)
+            .locality_ages
+            .extend(records);
+        self
+    }
+
+    /// Add locality status records, identified by `ls_id`.
+    pub fn with_locality_statuses(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures)
+            .locality_statuses
+            .extend(records);
+        self
+    }
+
+    /// Add locality type records, identified by `lt_id`.
+    pub fn with_locality_types(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures)
+            .locality_types
+            .extend(records);
+        self
+    }
+
+    /// Add geographic region records.
+    pub fn with_geo_regions(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures)
+            .geo_regions
+            .extend(records);
+        self
+    }
+
+    /// Add country records.
+    pub fn with_countries(mut self, records: impl IntoIterator<Item = Value>) -> Self {
+        Arc::make_mut(&mut self.fixtures).countries.extend(records);
+        self
+    }
+
+    /// Serve a fixed response at `path`, such as `"/dana-8/groups/"` or
+    /// `"/photo-count/"`.
+    ///
+    /// Documents take precedence over the built-in endpoints.
+    pub fn with_document(mut self, path: &str, body: Value) -> Self {
+        Arc::make_mut(&mut self.fixtures)
+            .documents
+            .insert(normalize_path(path), body);
+        self
+    }
+
+    /// Answer the next request with `status` instead of serving it.
+    ///
+    /// Calls queue up, one failure per request.
+    pub fn fail_next(&self, status: StatusCode) {
+        self.state
+            .lock()
+            .expect("fake API lock poisoned")
+            .failures
+            .push_back(status);
+    }
+
+    /// URLs of the requests received so far, in order.
+    pub fn requests(&self) -> Vec<Url> {
+        self.state
+            .lock()
+            .expect("fake API lock poisoned")
+            .requests
+            .clone()
+    }
+
+    /// A client builder pointed at this fake, with its token if one is set.
+    pub fn client_builder(&self) -> MindatClientBuilder {
+        let builder = MindatClient::builder()
+            .base_url(self.base_url.as_str())
+            .transport(self.clone());
+        match &self.token {
+            Some(token) => builder.token(token.as_str()),
+            None => builder,
+        }
+    }
+
+    /// Build the response to a request.
+    fn respond(&self, request: &TransportRequest) -> TransportResponse {
+        let url = &request.url;
+        let (failure, over_quota) = {
+            let mut state = self.state.lock().expect("fake API lock poisoned");
+            state.requests.push(url.clone());
+            let over_quota = self.quota.is_some_and(|quota| state.requests.len() > quota);
+            (state.failures.pop_front(), over_quota)
+        };
+        if let Some(status) = failure {
+            return error(url, status);
+        }
+        if over_quota {
+            return error(url, StatusCode::TOO_MANY_REQUESTS);
+        }
+
+        let Some(path) = url.path().strip_prefix(self.base_url.path()) else {
+            return error(url, StatusCode::NOT_FOUND);
+        };
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if segments.first() != Some(&Collection::MineralsIma.path()) {
+            if let Some(denied) = self.check_token(request) {
+                return denied;
+            }
+        }
+
+        if let Some(body) = self.fixtures.documents.get(&normalize_path(path)) {
+            return respond_json(url, StatusCode::OK, body.clone());
+        }
+        let params = Params::new(url);
+        match segments.as_slice() {
+            [name] => match Collection::from_path(name) {
+                Some(collection) => self.list(collection, url, &params),
+                None => error(url, StatusCode::NOT_FOUND),
+            },
+            [name, id] => match Collection::from_path(name) {
+                Some(collection) => self.detail(collection, id, url, &params),
+                None => error(url, StatusCode::NOT_FOUND),
+            },
+            _ => error(url, StatusCode::NOT_FOUND),
+        }
+    }
+
+    /// A 401 response if the request lacks the configured token.
+    fn check_token(&self, request: &TransportRequest) -> Option<TransportResponse> {
+        let token = self.token.as_ref()?;
+        let detail = match request.headers.get(AUTHORIZATION) {
+            None => "Authentication credentials were not provided.",
+            Some(value) if value.as_bytes() == format!("Token {}", token).as_bytes() => {
+                return None;
+            }
+            Some(_) => "Invalid token.",
+        };
+        let mut response = respond_json(
+            &request.url,
+            StatusCode::UNAUTHORIZED,
+            json!({ "detail": detail }),
+        );
+        response
+            .headers
+            .insert("www-authenticate", HeaderValue::from_static("Token"));
+        Some(response)
+    }
+
+    /// Serve a page of a list endpoint.
+    fn list(&self, collection: Collection, url: &Url, params: &Params) -> TransportResponse {
+        let mut records: Vec<&Value> = collection
+            .records(&self.fixtures)
+            .iter()
+            .filter(|record| params.matches(record, collection))
+            .collect();
+        if let Some(ordering) = params.get("ordering") {
+            sort(&mut records, ordering);
+        }
+        let page_size = params
+            .get("page_size")
+            .and_then(|size| size.parse::<usize>().ok())
+            .filter(|size| *size > 0)
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .min(MAX_PAGE_SIZE);
+
+        if collection.uses_cursor() && params.get("page").is_none() {
+            let offset = match params.get("cursor").map(decode_cursor) {
+                Some(Some(offset)) => offset,
+                Some(None) => return not_found(url, "Invalid cursor"),
+                None => 0,
+            };
+            let end = (offset + page_size).min(records.len());
+            let results = project(records.get(offset..end).unwrap_or_default(), params);
+            let next = (end < records.len()).then(|| with_param(url, "cursor", encode_cursor(end)));
+            let previous = (offset > 0).then(|| {
+                with_param(
+                    url,
+                    "cursor",
+                    encode_cursor(offset.saturating_sub(page_size)),
+                )
+            });
+            return respond_json(
+                url,
+                StatusCode::OK,
+                json!({ "next": next, "previous": previous, "results": results }),
+            );
+        }
+
+        let pages = records.len().div_ceil(page_size).max(1);
+        let page = match params.get("page").map(str::parse::<usize>) {
+            Some(Ok(page)) if (1..=pages).contains(&page) => page,
+            Some(_) => return not_found(url, "Invalid page."),
+            None => 1,
+        };
+        let start = (page - 1) * page_size;
+        let end = (start + page_size).min(records.len());
+        let results = project(&records[start..end], params);
+        let next = (page < pages).then(|| with_param(url, "page", (page + 1).to_string()));
+        let previous = (page > 1).then(|| with_param(url, "page", (page - 1).to_string()));
+        respond_json(
+            url,
+            StatusCode::OK,
+            json!({
+                "count": records.len(),
+                "next": next,
+                "previous": previous,
+                "results": results,
+            }),
+        )
+    }
+
+    /// Serve a single record by ID.
+    fn detail(
+        &self,
+        collection: Collection,
+        id: &str,
+        url: &Url,
+        params: &Params,
+    ) -> TransportResponse {
+        let record = collection.records(&self.fixtures).iter().find(|record| {
+            record
+                .get(collection.id_field())
+                .is_some_and(|field| matches_value(field, id))
+        });
+        match record {
+            Some(record) => respond_json(url, StatusCode::OK, project(&[record], params).remove(0)),
+            None => error(url, StatusCode::NOT_FOUND),
+        }
+    }
+}
+
+impl Default for FakeMindat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for FakeMindat {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>> {
+        Box::pin(async move { Ok(self.respond(&request)) })
+    }
+}
+
+/// Endpoints backed by fixture records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Collection {
+    Geomaterials,
+    Localities,
+    MineralsIma,
+    LocalityAges,
+    LocalityStatuses,
+    LocalityTypes,
+    GeoRegions,
+    Countries,
+}
+
+impl Collection {
+    const ALL: [Collection; 8] = [
+        Collection::Geomaterials,
+        Collection::Localities,
+        Collection::MineralsIma,
+        Collection::LocalityAges,
+        Collection::LocalityStatuses,
+        Collection::LocalityTypes,
+        Collection::GeoRegions,
+        Collection::Countries,
+    ];
+
+    fn from_path(path: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|collection| collection.path() == path)
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Collection::Geomaterials => "geomaterials",
+            Collection::Localities => "localities",
+            Collection::MineralsIma => "minerals-ima",
+            Collection::LocalityAges => "locality-age",
+            Collection::LocalityStatuses => "locality-status",
+            Collection::LocalityTypes => "locality-type",
+            Collection::GeoRegions => "locgeoregion2",
+            Collection::Countries => "countries",
+        }
+    }
+
+    fn records(self, fixtures: &Fixtures) -> &[Value] {
+        match self {
+            Collection::Geomaterials => &fixtures.geomaterials,
+            Collection::Localities => &fixtures.localities,
+            Collection::MineralsIma => &fixtures.minerals_ima,
+            Collection::LocalityAges => &fixtures.locality_ages,
+            Collection::LocalityStatuses => &fixtures.locality_statuses,
+            Collection::LocalityTypes => &fixtures.locality_types,
+            Collection::GeoRegions => &fixtures.geo_regions,
+            Collection::Countries => &fixtures.countries,
+        }
+    }
+
+    fn id_field(self) -> &'static str {
+        match self {
+            Collection::LocalityAges => "age_id",
+            Collection::LocalityStatuses => "ls_id",
+            Collection::LocalityTypes => "lt_id",
+            _ => "id",
+        }
+    }
+
+    /// Field searched by the `q` parameter.
+    fn name_field(self) -> &'static str {
+        match self {
+            Collection::Localities => "txt",
+            Collection::Countries => "text",
+            _ => "name",
+        }
+    }
+
+    fn uses_cursor(self) -> bool {
+        self == Collection::Localities
+    }
+}
+
+/// Query parameters of a request.
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn new(url: &Url) -> Self {
+        Self(url.query_pairs().into_owned().collect())
+    }
+
+    /// The first value of a parameter.
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether a record passes every filter parameter.
+    fn matches(&self, record: &Value, collection: Collection) -> bool {
+        self.0.iter().all(|(key, _)| {
+            let values: Vec<&str> = self
+                .0
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .collect();
+            filter_matches(record, collection, key, &values)
+        })
+    }
+
+    fn list(&self, key: &str) -> Option<Vec<&str>> {
+        self.get(key).map(split_list)
+    }
+}
+
+/// Apply one filter parameter, with all its values, to a record.
+fn filter_matches(record: &Value, collection: Collection, key: &str, values: &[&str]) -> bool {
+    let value = values[0];
+    match key {
+        "id__in" => {
+            let id = record.get(collection.id_field());
+            split_list(value)
+                .into_iter()
+                .any(|v| id.is_some_and(|id| matches_value(id, v)))
+        }
+        "q" => contains(record, collection.name_field(), value),
+        "description" => contains(record, "description_short", value),
+        "name" | "txt" | "colour" | "streak" | "country" => contains(record, key, value),
+        "ima" => {
+            let approved = matches!(value.to_ascii_lowercase().as_str(), "1" | "true");
+            let is_approved = record
+                .get("ima_status")
+                .is_some_and(|status| matches_value(status, "APPROVED"));
+            approved == is_approved
+        }
+        "elements_inc" => {
+            let elements = elements(record);
+            split_list(value)
+                .into_iter()
+                .all(|e| elements.iter().any(|have| have.eq_ignore_ascii_case(e)))
+        }
+        "elements_exc" => {
+            let elements = elements(record);
+            !split_list(value)
+                .into_iter()
+                .any(|e| elements.iter().any(|have| have.eq_ignore_ascii_case(e)))
+        }
+        "hardness_min" => in_range(
+            number(record, "hmax").or(number(record, "hmin")),
+            value,
+            true,
+        ),
+        "hardness_max" => in_range(
+            number(record, "hmin").or(number(record, "hmax")),
+            value,
+            false,
+        ),
+        "density_min" => in_range(number(record, "dmeas"), value, true),
+        "density_max" => in_range(number(record, "dmeas"), value, false),
+        key if IGNORED_PARAMS.contains(&key) || key.ends_with("_min") || key.ends_with("_max") => {
+            true
+        }
+        key => {
+            let field = if key == "crystal_system" {
+                "csystem"
+            } else {
+                key
+            };
+            record
+                .get(field)
+                .is_some_and(|field| values.iter().any(|v| matches_value(field, v)))
+        }
+    }
+}
+
+/// Whether a field equals a query value, or contains it if it is an array.
+fn matches_value(field: &Value, value: &str) -> bool {
+    match field {
+        Value::Array(items) => items.iter().any(|item| matches_value(item, value)),
+        Value::String(s) => s.eq_ignore_ascii_case(value),
+        Value::Number(n) => match (n.as_f64(), value.parse::<f64>()) {
+            (Some(n), Ok(v)) => n == v,
+            _ => false,
+        },
+        Value::Bool(b) => match value.to_ascii_lowercase().as_str() {
+            "true" | "1" => *b,
+            "false" | "0" => !*b,
+            _ => false,
+        },
+        Value::Null | Value::Object(_) => false,
+    }
+}
+
+/// Case-insensitive substring match on a string field.
+fn contains(record: &Value, field: &str, value: &str) -> bool {
+    record
+        .get(field)
+        .and_then(Value::as_str)
+        .is_some_and(|s| s.to_lowercase().contains(&value.to_lowercase()))
+}
+
+/// A numeric field, which the API sometimes returns as a string.
+fn number(record: &Value, field: &str) -> Option<f64> {
+    match record.get(field)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether a number is at least (or at most) a query value.
+fn in_range(number: Option<f64>, value: &str, min: bool) -> bool {
+    match (number, value.parse::<f64>()) {
+        (Some(n), Ok(limit)) if min => n >= limit,
+        (Some(n), Ok(limit)) => n <= limit,
+        _ => false,
+    }
+}
+
+/// The element symbols of a record, from an array or a delimited string.
+fn elements(record: &Value) -> Vec<&str> {
+    match record.get("elements") {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        Some(Value::String(s)) => s
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .filter(|e| !e.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Sort records by a comma-separated `ordering` value; missing fields last.
+fn sort(records: &mut [&Value], ordering: &str) {
+    let keys: Vec<(&str, bool)> = split_list(ordering)
+        .into_iter()
+        .map(|key| match key.strip_prefix('-') {
+            Some(key) => (key, true),
+            None => (key, false),
+        })
+        .collect();
+    records.sort_by(|a, b| {
+        keys.iter()
+            .map(|(key, descending)| match (a.get(*key), b.get(*key)) {
+                (Some(a), Some(b)) if *descending => compare(b, a),
+                (Some(a), Some(b)) => compare(a, b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// Apply the `fields` and `omit` parameters to records.
+fn project(records: &[&Value], params: &Params) -> Vec<Value> {
+    let fields = params.list("fields");
+    let omit = params.list("omit").unwrap_or_default();
+    records
+        .iter()
+        .map(|record| match record {
+            Value::Object(object) => {
+                let projected: Map<String, Value> = object
+                    .iter()
+                    .filter(|(key, _)| fields.as_ref().is_none_or(|f| f.contains(&key.as_str())))
+                    .filter(|(key, _)| !omit.contains(&key.as_str()))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                Value::Object(projected)
+            }
+            record => (*record).clone(),
+        })
+        .collect()
+}
+
+fn encode_cursor(offset: usize) -> String {
+    format!("o{}", offset)
+}
+
+fn decode_cursor(cursor: &str) -> Option<usize> {
+    cursor.strip_prefix('o')?.parse().ok()
+}
+
+/// The URL with one query parameter set, dropping the pagination parameter
+/// of the other style.
+fn with_param(url: &Url, key: &str, value: String) -> String {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .into_owned()
+        .filter(|(k, _)| k != key && k != "page" && k != "cursor")
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(key, &value);
+    url.to_string()
+}
+
+/// A path relative to the base URL, without a leading slash and with a
+/// trailing one.
+fn normalize_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    format!("{}/", path)
+}
+
+fn respond_json(url: &Url, status: StatusCode, body: Value) -> TransportResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    TransportResponse {
+        status,
+        headers,
+        body: body.to_string().into(),
+        url: url.clone(),
+    }
+}
+
+fn not_found(url: &Url, detail: &str) -> TransportResponse {
+    respond_json(url, StatusCode::NOT_FOUND, json!({ "detail": detail }))
+}
+
+/// An error response with the API's usual `detail` message.
+fn error(url: &Url, status: StatusCode) -> TransportResponse {
+    let detail = match status {
+        StatusCode::NOT_FOUND => "Not found.",
+        StatusCode::UNAUTHORIZED => "Authentication credentials were not provided.",
+        StatusCode::TOO_MANY_REQUESTS => "Request was throttled. Expected available in 1 second.",
+        status => status.canonical_reason().unwrap_or("Error"),
+    };
+    let mut response = respond_json(url, status, json!({ "detail": detail }));
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        response
+            .headers
+            .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_matches_values_and_arrays() {
+        let record = json!({"id": 3337, "ima_status": ["APPROVED"], "entrytype": 0});
+        assert!(filter_matches(
+            &record,
+            Collection::Geomaterials,
+            "ima",
+            &["true"]
+        ));
+        assert!(filter_matches(
+            &record,
+            Collection::Geomaterials,
+            "entrytype",
+            &["7", "0"]
+        ));
+        assert!(!filter_matches(
+            &record,
+            Collection::Geomaterials,
+            "entrytype",
+            &["7"]
+        ));
+        assert!(filter_matches(
+            &record,
+            Collection::Geomaterials,
+            "id__in",
+            &["1,3337"]
+        ));
+        assert!(!filter_matches(
+            &record,
+            Collection::Geomaterials,
+            "csystem",
+            &["Trigonal"]
+        ));
+    }
+
+    #[test]
+    fn test_locality_elements_are_split() {
+        let record = json!({"id": 1, "elements": "Au,Cu,Fe,S"});
+        assert_eq!(elements(&record), vec!["Au", "Cu", "Fe", "S"]);
+    }
+}
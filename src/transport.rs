@@ -0,0 +1,252 @@
+//! Pluggable HTTP transport.
+//!
+//! Every request made by [`MindatClient`](crate::MindatClient) goes through a
+//! [`Transport`]. By default this is a [`ReqwestTransport`] configured from
+//! the [`MindatClientBuilder`](crate::MindatClientBuilder) options; a custom
+//! implementation can be supplied with
+//! [`MindatClientBuilder::transport`](crate::MindatClientBuilder::transport),
+//! for example to serve canned responses in tests or to use a different HTTP
+//! stack.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::future::BoxFuture;
+//! use mindat_rs::transport::{Transport, TransportRequest, TransportResponse};
+//! use mindat_rs::{MindatClient, Result};
+//! use reqwest::StatusCode;
+//! use reqwest::header::HeaderMap;
+//!
+//! /// Answers every request with an empty page.
+//! #[derive(Debug)]
+//! struct EmptyPages;
+//!
+//! impl Transport for EmptyPages {
+//!     fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>> {
+//!         Box::pin(async move {
+//!             Ok(TransportResponse {
+//!                 status: StatusCode::OK,
+//!                 headers: HeaderMap::new(),
+//!                 body: r#"{"next": null, "results": []}"#.into(),
+//!                 url: request.url,
+//!             })
+//!         })
+//!     }
+//! }
+//!
+//! let client = MindatClient::builder().transport(EmptyPages).build()?;
+//! # Ok::<(), mindat_rs::MindatError>(())
+//! ```
+
+use bytes::{Bytes, BytesMut};
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, StatusCode};
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+use crate::error::{MindatError, Result};
+
+/// Description of an HTTP request to send.
+#[derive(Debug, Clone)]
+pub struct TransportRequest {
+    /// HTTP method.
+    pub method: Method,
+    /// Full request URL, including the query string.
+    pub url: Url,
+    /// Request headers, including authentication.
+    pub headers: HeaderMap,
+}
+
+/// An HTTP response with its body fully read.
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    /// HTTP status code.
+    pub status: StatusCode,
+    /// Response headers.
+    pub headers: HeaderMap,
+    /// Response body.
+    pub body: Bytes,
+    /// Final URL of the response, after any redirects.
+    pub url: Url,
+}
+
+/// A response body read chunk by chunk.
+pub type BodyStream = BoxStream<'static, Result<Bytes>>;
+
+/// An HTTP response whose body has not been read yet.
+pub struct StreamingResponse {
+    /// HTTP status code.
+    pub status: StatusCode,
+    /// Response headers.
+    pub headers: HeaderMap,
+    /// Response body.
+    pub body: BodyStream,
+    /// Final URL of the response, after any redirects.
+    pub url: Url,
+}
+
+impl StreamingResponse {
+    /// Whether this is a successful response with a body that is not HTML,
+    /// i.e. one whose body should be streamed rather than read whole.
+    pub(crate) fn is_streamable(&self) -> bool {
+        let is_html = self
+            .headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.to_ascii_lowercase().contains("html"));
+        self.status.is_success() && !is_html
+    }
+
+    /// Read the rest of the body.
+    pub async fn collect(self) -> Result<TransportResponse> {
+        let body = self
+            .body
+            .try_fold(BytesMut::new(), |mut body, chunk| async move {
+                body.extend_from_slice(&chunk);
+                Ok(body)
+            })
+            .await?;
+        Ok(TransportResponse {
+            status: self.status,
+            headers: self.headers,
+            body: body.freeze(),
+            url: self.url,
+        })
+    }
+}
+
+impl From<TransportResponse> for StreamingResponse {
+    fn from(response: TransportResponse) -> Self {
+        Self {
+            status: response.status,
+            headers: response.headers,
+            body: stream::once(async move { Ok(response.body) }).boxed(),
+            url: response.url,
+        }
+    }
+}
+
+impl fmt::Debug for StreamingResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sends HTTP requests on behalf of the client.
+///
+/// Implementations should only return an error when no response could be
+/// obtained; non-success statuses are returned as regular responses so the
+/// client can retry or map them to a [`MindatError`].
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Send a request and read the whole response.
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>>;
+
+    /// Send a request and return the response without reading its body.
+    ///
+    /// Used for streamed pages. The default implementation reads the whole
+    /// response with [`send`](Self::send) and hands the body over as a
+    /// single chunk.
+    fn send_streaming(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'_, Result<StreamingResponse>> {
+        Box::pin(async move { self.send(request).await.map(StreamingResponse::from) })
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>> {
+        (**self).send(request)
+    }
+
+    fn send_streaming(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'_, Result<StreamingResponse>> {
+        (**self).send_streaming(request)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>> {
+        (**self).send(request)
+    }
+
+    fn send_streaming(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'_, Result<StreamingResponse>> {
+        (**self).send_streaming(request)
+    }
+}
+
+/// The default transport, backed by a `reqwest` client.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    /// Wrap a configured `reqwest` client.
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Get the underlying `reqwest` client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .request(request.method, request.url)
+                .headers(request.headers)
+                .send()
+                .await?;
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            let url = response.url().clone();
+            let body = response.bytes().await?;
+
+            Ok(TransportResponse {
+                status,
+                headers,
+                body,
+                url,
+            })
+        })
+    }
+
+    fn send_streaming(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'_, Result<StreamingResponse>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .request(request.method, request.url)
+                .headers(request.headers)
+                .send()
+                .await?;
+
+            Ok(StreamingResponse {
+                status: response.status(),
+                headers: response.headers().clone(),
+                url: response.url().clone(),
+                body: response.bytes_stream().map_err(MindatError::from).boxed(),
+            })
+        })
+    }
+}
@@ -0,0 +1,84 @@
+//! Tests for fetching records by ID in batches.
+
+use mindat_rs::MindatClient;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Answers `id__in` queries with a geomaterial for every even ID.
+struct EvenIds;
+
+impl Respond for EvenIds {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let ids: Vec<i32> = request
+            .url
+            .query_pairs()
+            .find(|(key, _)| key == "id__in")
+            .map(|(_, value)| value.split(',').map(|id| id.parse().unwrap()).collect())
+            .unwrap_or_default();
+        // Reverse to check that results are put back in request order
+        let results: Vec<_> = ids
+            .iter()
+            .rev()
+            .filter(|id| *id % 2 == 0)
+            .map(|id| serde_json::json!({"id": id, "name": format!("Mineral {}", id)}))
+            .collect();
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": results.len(),
+            "next": null,
+            "previous": null,
+            "results": results
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_geomaterials_by_ids_chunks_and_reports_missing() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(EvenIds)
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .build()
+        .expect("Failed to build client");
+
+    // 250 distinct IDs, one duplicated
+    let mut ids: Vec<i32> = (1..=250).rev().collect();
+    ids.push(250);
+    let batch = client
+        .geomaterials_by_ids(&ids)
+        .await
+        .expect("Request failed");
+
+    let found: Vec<i32> = batch.results.iter().map(|m| m.id).collect();
+    let expected: Vec<i32> = (1..=250).rev().filter(|id| id % 2 == 0).collect();
+    assert_eq!(found, expected);
+    let missing: Vec<i32> = (1..=250).rev().filter(|id| id % 2 == 1).collect();
+    assert_eq!(batch.missing, missing);
+    assert!(!batch.is_complete());
+}
+
+#[tokio::test]
+async fn test_by_ids_without_ids_makes_no_requests() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(EvenIds)
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .build()
+        .expect("Failed to build client");
+
+    let batch = client.localities_by_ids(&[]).await.expect("Request failed");
+    assert!(batch.results.is_empty());
+    assert!(batch.is_complete());
+}
@@ -0,0 +1,95 @@
+//! Tests for the blocking client, using wiremock to mock the Mindat API.
+
+#![cfg(feature = "blocking")]
+
+use mindat_rs::blocking::MindatClient;
+use mindat_rs::{GeomaterialsQuery, MindatError};
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn start_mock_server(mocks: Vec<Mock>) -> (tokio::runtime::Runtime, MockServer) {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start runtime");
+    let mock_server = runtime.block_on(async {
+        let mock_server = MockServer::start().await;
+        for mock in mocks {
+            mock.mount(&mock_server).await;
+        }
+        mock_server
+    });
+    (runtime, mock_server)
+}
+
+#[test]
+fn test_blocking_geomaterial_by_id() {
+    let (_runtime, mock_server) = start_mock_server(vec![
+        Mock::given(method("GET"))
+            .and(path("/geomaterials/3337/"))
+            .and(header("Authorization", "Token test-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 3337,
+                "name": "Quartz",
+                "mindat_formula": "SiO2"
+            }))),
+    ]);
+
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .build_blocking()
+        .expect("Failed to build client");
+
+    let result = client.geomaterial(3337).expect("Request failed");
+    assert_eq!(result.id, 3337);
+    assert_eq!(result.name, Some("Quartz".to_string()));
+}
+
+#[test]
+fn test_blocking_geomaterials_iter() {
+    let (_runtime, mock_server) = start_mock_server(vec![
+        Mock::given(method("GET"))
+            .and(path("/geomaterials/"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "count": 2,
+                "next": null,
+                "previous": null,
+                "results": [{"id": 2, "name": "Covellite"}]
+            }))),
+        Mock::given(method("GET"))
+            .and(path("/geomaterials/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "count": 2,
+                "next": "http://example.com/geomaterials/?page=2",
+                "previous": null,
+                "results": [{"id": 1, "name": "Chalcopyrite"}]
+            }))),
+    ]);
+
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .build_blocking()
+        .expect("Failed to build client");
+
+    let ids: Vec<i32> = client
+        .geomaterials_iter(GeomaterialsQuery::new())
+        .map(|m| m.expect("Request failed").id)
+        .collect();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[test]
+fn test_blocking_not_found_error() {
+    let (_runtime, mock_server) = start_mock_server(vec![
+        Mock::given(method("GET"))
+            .and(path("/localities/1/"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("Not found")),
+    ]);
+
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .build_blocking()
+        .expect("Failed to build client");
+
+    let err = client.locality(1).unwrap_err();
+    assert!(matches!(err, MindatError::NotFound { .. }));
+}
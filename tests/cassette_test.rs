@@ -0,0 +1,140 @@
+//! Tests for recording and replaying cassettes.
+
+#![cfg(feature = "cassette")]
+
+use mindat_rs::cassette::CassetteMode;
+use mindat_rs::{ErrorKind, GeomaterialsQuery, MindatClient, MindatError};
+use std::path::PathBuf;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A cassette path for one test, removed if left over from an earlier run.
+fn cassette_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "mindat-rs-cassette-{}-{}.json",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn test_record_then_replay_without_network() {
+    let file = cassette_path("replay");
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 1,
+            "next": null,
+            "previous": null,
+            "results": [{"id": 3337, "name": "Quartz"}]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let recorder = MindatClient::builder()
+        .token("s3cr3t-token")
+        .base_url(&base_url)
+        .cassette(&file, CassetteMode::Record)
+        .build()
+        .expect("Failed to build client");
+    recorder
+        .geomaterials(GeomaterialsQuery::new().name("quartz").ima_approved(true))
+        .await
+        .expect("Request failed");
+    recorder.geomaterial(3337).await.expect("Request failed");
+    assert!(!file.exists(), "Cassette written before saving");
+    recorder.save_cassette().expect("Failed to save cassette");
+
+    let contents = std::fs::read_to_string(&file).expect("Cassette not written");
+    assert!(!contents.contains("s3cr3t-token"));
+
+    // The server is gone; every response must come from the cassette
+    drop(mock_server);
+    let player = MindatClient::builder()
+        .base_url(&base_url)
+        .cassette(&file, CassetteMode::Replay)
+        .build()
+        .expect("Failed to build client");
+
+    let page = player
+        .geomaterials(GeomaterialsQuery::new().ima_approved(true).name("quartz"))
+        .await
+        .expect("Replay failed");
+    assert_eq!(page.results[0].name, Some("Quartz".to_string()));
+    for _ in 0..2 {
+        let quartz = player.geomaterial(3337).await.expect("Replay failed");
+        assert_eq!(quartz.id, 3337);
+    }
+
+    let err = player.geomaterial(1).await.unwrap_err();
+    assert!(matches!(err, MindatError::CassetteMiss(_)));
+    assert_eq!(err.kind(), ErrorKind::Offline);
+    assert!(err.to_string().contains("/geomaterials/1/"));
+
+    let _ = std::fs::remove_file(&file);
+}
+
+#[tokio::test]
+async fn test_replay_keeps_recorded_order() {
+    let file = cassette_path("order");
+    let mock_server = MockServer::start().await;
+    let base_url = mock_server.uri();
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"count": 42})))
+        .mount(&mock_server)
+        .await;
+
+    let recorder = MindatClient::builder()
+        .base_url(&base_url)
+        .cassette(&file, CassetteMode::Record)
+        .build()
+        .expect("Failed to build client");
+    assert!(recorder.photocount().await.is_err());
+    recorder.photocount().await.expect("Request failed");
+    // Dropping the recorder saves the cassette
+    drop(recorder);
+    drop(mock_server);
+
+    let player = MindatClient::builder()
+        .base_url(&base_url)
+        .cassette(&file, CassetteMode::Replay)
+        .build()
+        .expect("Failed to build client");
+    let err = player.photocount().await.unwrap_err();
+    assert!(matches!(err, MindatError::Api { status: 503, .. }));
+    let count = player.photocount().await.expect("Replay failed");
+    assert_eq!(count["count"], 42);
+
+    let _ = std::fs::remove_file(&file);
+}
+
+#[test]
+fn test_replay_of_missing_cassette_fails() {
+    let result = MindatClient::builder()
+        .cassette(cassette_path("missing"), CassetteMode::Replay)
+        .build();
+    assert!(matches!(result, Err(MindatError::Io(_))));
+}
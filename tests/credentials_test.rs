@@ -0,0 +1,140 @@
+//! Tests for credential providers and configuration profiles.
+
+use mindat_rs::credentials::{CallbackProvider, ConfigFile, ConfigFileProvider};
+use mindat_rs::{
+    Middleware, MindatClient, MindatError, Result, TransportRequest, TransportResponse,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Quartz, answered only when sent with `token`.
+fn quartz(token: &str) -> Mock {
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .and(header("Authorization", format!("Token {}", token).as_str()))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+}
+
+#[tokio::test]
+async fn test_callback_provider_is_asked_per_request() {
+    let mock_server = MockServer::start().await;
+    quartz("token-0").expect(1).mount(&mock_server).await;
+    quartz("token-1").expect(1).mount(&mock_server).await;
+
+    let calls = AtomicUsize::new(0);
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .credentials(CallbackProvider::new(move || {
+            let n = calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(format!("token-{}", n)))
+        }))
+        .build()
+        .expect("Failed to build client");
+
+    for _ in 0..2 {
+        client.geomaterial(3337).await.expect("Request failed");
+    }
+}
+
+#[tokio::test]
+async fn test_provider_error_fails_request() {
+    let client = MindatClient::builder()
+        .base_url("http://127.0.0.1:9/")
+        .credentials(CallbackProvider::new(|| {
+            Err::<Option<String>, _>(MindatError::Config("vault is sealed".to_string()))
+        }))
+        .build()
+        .expect("Failed to build client");
+
+    let err = client.geomaterial(3337).await.unwrap_err();
+    assert!(matches!(err, MindatError::Config(_)));
+}
+
+#[tokio::test]
+async fn test_client_from_profile_settings() {
+    let mock_server = MockServer::start().await;
+    quartz("work-token").expect(1).mount(&mock_server).await;
+
+    let config = ConfigFile::parse(&format!(
+        "[work]\ntoken = \"work-token\"\nbase_url = \"{}\"\ntimeout = 10\n",
+        mock_server.uri()
+    ))
+    .unwrap();
+    let client = MindatClient::builder()
+        .profile(config.profile("work").unwrap())
+        .build()
+        .expect("Failed to build client");
+
+    let mineral = client.geomaterial(3337).await.expect("Request failed");
+    assert_eq!(mineral.name, Some("Quartz".to_string()));
+}
+
+#[tokio::test]
+async fn test_config_file_provider() {
+    let mock_server = MockServer::start().await;
+    quartz("file-token").expect(1).mount(&mock_server).await;
+
+    let file = std::env::temp_dir().join(format!("mindat-rs-config-{}.toml", std::process::id()));
+    std::fs::write(&file, "[default]\ntoken = \"file-token\"\n").unwrap();
+    let provider = ConfigFileProvider::from_path(&file, "default");
+    let _ = std::fs::remove_file(&file);
+
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .credentials(provider.expect("Failed to read config"))
+        .build()
+        .expect("Failed to build client");
+    client.geomaterial(3337).await.expect("Request failed");
+}
+
+#[test]
+fn test_debug_output_does_not_leak_token() {
+    const TOKEN: &str = "s3cr3t-api-token";
+
+    let builder = MindatClient::builder().token(TOKEN);
+    assert!(!format!("{:?}", builder).contains(TOKEN));
+
+    let mut client = builder.build().expect("Failed to build client");
+    assert!(!format!("{:?}", client).contains(TOKEN));
+    assert!(!format!("{:#?}", client).contains(TOKEN));
+
+    client.set_token(TOKEN.to_string());
+    assert!(!format!("{:?}", client).contains(TOKEN));
+}
+
+/// Records the debug output of every request it sees.
+#[derive(Debug, Default)]
+struct DebugRecorder(Mutex<Vec<String>>);
+
+impl Middleware for DebugRecorder {
+    fn before_request(&self, request: &mut TransportRequest) -> Result<Option<TransportResponse>> {
+        self.0.lock().unwrap().push(format!("{:?}", request));
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_request_debug_output_does_not_leak_token() {
+    const TOKEN: &str = "s3cr3t-api-token";
+    let mock_server = MockServer::start().await;
+    quartz(TOKEN).expect(1).mount(&mock_server).await;
+
+    let recorder = Arc::new(DebugRecorder::default());
+    let client = MindatClient::builder()
+        .token(TOKEN)
+        .base_url(mock_server.uri())
+        .middleware(recorder.clone())
+        .build()
+        .expect("Failed to build client");
+    client.geomaterial(3337).await.expect("Request failed");
+
+    let seen = recorder.0.lock().unwrap();
+    assert_eq!(seen.len(), 1);
+    assert!(seen[0].contains("authorization"));
+    assert!(!seen[0].contains(TOKEN));
+}
@@ -0,0 +1,184 @@
+//! Tests for the on-disk response cache.
+
+#![cfg(feature = "disk-cache")]
+
+use futures::{StreamExt, TryStreamExt};
+use mindat_rs::disk_cache::{DiskCacheConfig, DiskCacheMode};
+use mindat_rs::{GeomaterialsQuery, MindatClient, MindatError};
+use std::path::PathBuf;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A fresh cache directory for one test.
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mindat-rs-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn client(server: &MockServer, dir: &PathBuf, mode: DiskCacheMode) -> MindatClient {
+    MindatClient::builder()
+        .token("test-token")
+        .base_url(server.uri())
+        .disk_cache(DiskCacheConfig::new(dir).mode(mode))
+        .build()
+        .expect("Failed to build client")
+}
+
+#[tokio::test]
+async fn test_offline_mode_reads_responses_written_online() {
+    let mock_server = MockServer::start().await;
+    let dir = cache_dir("offline");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 1,
+            "next": null,
+            "previous": null,
+            "results": [{"id": 3337, "name": "Quartz"}]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let online = client(&mock_server, &dir, DiskCacheMode::WriteThrough);
+    online
+        .geomaterials(GeomaterialsQuery::new().name("quartz").page(1))
+        .await
+        .expect("Request failed");
+
+    // Same query with its parameters in a different order
+    let offline = client(&mock_server, &dir, DiskCacheMode::OfflineOnly);
+    let result = offline
+        .geomaterials(GeomaterialsQuery::new().page(1).name("quartz"))
+        .await
+        .expect("Cached response missing");
+    assert_eq!(result.results[0].name, Some("Quartz".to_string()));
+
+    let err = offline
+        .geomaterials(GeomaterialsQuery::new().name("calcite"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MindatError::OfflineCacheMiss(_)));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_entries_are_kept_apart_by_credentials() {
+    let mock_server = MockServer::start().await;
+    let dir = cache_dir("credentials");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let online = client(&mock_server, &dir, DiskCacheMode::WriteThrough);
+    online.geomaterial(3337).await.expect("Request failed");
+
+    let offline = |token: Option<&str>| {
+        let builder = MindatClient::builder()
+            .base_url(mock_server.uri())
+            .disk_cache(DiskCacheConfig::new(&dir).mode(DiskCacheMode::OfflineOnly));
+        match token {
+            Some(token) => builder.token(token),
+            None => builder,
+        }
+        .build()
+        .expect("Failed to build client")
+    };
+    offline(Some("test-token"))
+        .geomaterial(3337)
+        .await
+        .expect("Cached response missing");
+    for token in [Some("other-token"), None] {
+        let err = offline(token).geomaterial(3337).await.unwrap_err();
+        assert!(matches!(err, MindatError::OfflineCacheMiss(_)));
+    }
+
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let contents = std::fs::read(entry.unwrap().path()).unwrap();
+        let mut decoded = Vec::new();
+        std::io::Read::read_to_end(
+            &mut flate2::read::GzDecoder::new(&contents[..]),
+            &mut decoded,
+        )
+        .unwrap();
+        assert!(!String::from_utf8_lossy(&decoded).contains("test-token"));
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_read_through_fetches_once() {
+    let mock_server = MockServer::start().await;
+    let dir = cache_dir("read-through");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let client = client(&mock_server, &dir, DiskCacheMode::ReadThrough);
+    for _ in 0..3 {
+        let mineral = client.geomaterial(3337).await.expect("Request failed");
+        assert_eq!(mineral.name, Some("Quartz".to_string()));
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_offline_items_stream_never_contacts_api() {
+    let mock_server = MockServer::start().await;
+    let dir = cache_dir("offline-items");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 2,
+            "next": null,
+            "previous": null,
+            "results": [{"id": 3337, "name": "Quartz"}, {"id": 859, "name": "Calcite"}]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let online = client(&mock_server, &dir, DiskCacheMode::WriteThrough);
+    let minerals: Vec<_> = online
+        .geomaterials_items(GeomaterialsQuery::new().name("quartz"))
+        .try_collect()
+        .await
+        .expect("Stream failed");
+    assert_eq!(minerals.len(), 2);
+
+    let offline = client(&mock_server, &dir, DiskCacheMode::OfflineOnly);
+    let minerals: Vec<_> = offline
+        .geomaterials_items(GeomaterialsQuery::new().name("quartz"))
+        .try_collect()
+        .await
+        .expect("Cached page missing");
+    assert_eq!(minerals[1].name, Some("Calcite".to_string()));
+
+    let mut stream = offline.geomaterials_items(GeomaterialsQuery::new().name("calcite"));
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(MindatError::OfflineCacheMiss(_)))
+    ));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
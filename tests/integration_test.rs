@@ -1,7 +1,13 @@
 //! Integration tests using wiremock to mock the Mindat API.
 
-use mindat_rs::{GeomaterialsQuery, ImaMineralsQuery, LocalitiesQuery, MindatClient};
-use wiremock::matchers::{header, method, path, query_param};
+use futures::{StreamExt, TryStreamExt};
+use mindat_rs::{
+    CacheConfig, CrystalSystem, DecodeMode, GeomaterialsQuery, ImaMineralsQuery, ImaStatus,
+    LocalitiesQuery, LustreType, Middleware, MindatApi, MindatClient, OpticalSign, RateLimit,
+    Result, RetryPolicy, Tenacity, TransportRequest, TransportResponse,
+};
+use std::time::Duration;
+use wiremock::matchers::{header, header_regex, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 async fn setup_mock_client() -> (MockServer, MindatClient) {
@@ -121,6 +127,70 @@ async fn test_geomaterials_with_elements() {
     assert_eq!(result.results[0].name, Some("Chalcopyrite".to_string()));
 }
 
+#[tokio::test]
+async fn test_geomaterials_multi_value_filters() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .and(query_param("crystal_system", "Hexagonal"))
+        .and(query_param("crystal_system", "Trigonal"))
+        .and(query_param("entrytype", "0"))
+        .and(query_param("entrytype", "7"))
+        .and(query_param("ima_status", "APPROVED"))
+        .and(query_param("tenacity", "very brittle"))
+        .and(query_param("lustretype", "Sub-Vitreous"))
+        .and(query_param("opticalsign", "+"))
+        .and(query_param("id__in", "1,2,3"))
+        .and(query_param("expand", "locality,relations"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 1,
+            "next": null,
+            "previous": null,
+            "results": [
+                {"id": 1, "name": "Quartz"}
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let query = GeomaterialsQuery::new()
+        .crystal_systems(vec![CrystalSystem::Hexagonal, CrystalSystem::Trigonal])
+        .entry_types(vec![0, 7])
+        .ima_statuses(vec![ImaStatus::Approved])
+        .tenacity(vec![Tenacity::VeryBrittle])
+        .lustre_types(vec![LustreType::SubVitreous])
+        .optical_sign(OpticalSign::Positive)
+        .ids(vec![1, 2, 3])
+        .expand_fields(vec!["locality".to_string(), "relations".to_string()]);
+    let result = client.geomaterials(query).await.expect("Request failed");
+
+    assert_eq!(result.results.len(), 1);
+}
+
+#[tokio::test]
+async fn test_minerals_ima_filters() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/minerals-ima/"))
+        .and(query_param("ima", "1"))
+        .and(query_param("id__in", "10,20"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 0,
+            "next": null,
+            "previous": null,
+            "results": []
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let query = ImaMineralsQuery::new().ima(1).ids(vec![10, 20]);
+    let result = client.minerals_ima(query).await.expect("Request failed");
+
+    assert!(result.results.is_empty());
+}
+
 #[tokio::test]
 async fn test_localities_by_country() {
     let (mock_server, client) = setup_mock_client().await;
@@ -199,7 +269,7 @@ async fn test_auth_required_error() {
     let err = result.unwrap_err();
     assert!(matches!(
         err,
-        mindat_rs::MindatError::AuthenticationRequired
+        mindat_rs::MindatError::AuthenticationRequired { .. }
     ));
 }
 
@@ -217,7 +287,7 @@ async fn test_rate_limit_error() {
     assert!(result.is_err());
 
     let err = result.unwrap_err();
-    assert!(matches!(err, mindat_rs::MindatError::RateLimited));
+    assert!(matches!(err, mindat_rs::MindatError::RateLimited { .. }));
 }
 
 #[tokio::test]
@@ -233,8 +303,14 @@ async fn test_not_found_error() {
     let result = client.geomaterial(99999999).await;
     assert!(result.is_err());
 
-    let err = result.unwrap_err();
-    assert!(matches!(err, mindat_rs::MindatError::NotFound(_)));
+    match result.unwrap_err() {
+        mindat_rs::MindatError::NotFound { message, details } => {
+            assert_eq!(message, "Not found");
+            assert_eq!(details.status, 404);
+            assert!(details.url.ends_with("/geomaterials/99999999/"));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
 }
 
 #[tokio::test]
@@ -262,3 +338,1276 @@ async fn test_pagination() {
     assert!(!result.has_previous());
     assert_eq!(result.total_pages(10), Some(3));
 }
+
+#[tokio::test]
+async fn test_geomaterials_stream_follows_next() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 3,
+            "next": null,
+            "previous": "http://example.com/geomaterials/?elements_inc=Cu&page_size=2",
+            "results": [
+                {"id": 3, "name": "Cuprite"}
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .and(query_param("elements_inc", "Cu"))
+        .and(query_param("page_size", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 3,
+            "next": "http://example.com/geomaterials/?elements_inc=Cu&page=2&page_size=2",
+            "previous": null,
+            "results": [
+                {"id": 1, "name": "Chalcopyrite"},
+                {"id": 2, "name": "Covellite"}
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let query = GeomaterialsQuery::new().with_elements("Cu").page_size(2);
+    let minerals: Vec<_> = client
+        .geomaterials_stream(query)
+        .try_collect()
+        .await
+        .expect("Stream failed");
+
+    let ids: Vec<i32> = minerals.iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_minerals_ima_stream_max_items() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/minerals-ima/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 6000,
+            "next": "http://example.com/minerals-ima/?page=2",
+            "previous": null,
+            "results": [
+                {"id": 1, "name": "Abelsonite"},
+                {"id": 2, "name": "Abenakiite-(Ce)"},
+                {"id": 3, "name": "Abernathyite"}
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let minerals: Vec<_> = client
+        .minerals_ima_stream(ImaMineralsQuery::new())
+        .max_items(2)
+        .prefetch(0)
+        .try_collect()
+        .await
+        .expect("Stream failed");
+
+    assert_eq!(minerals.len(), 2);
+    assert_eq!(minerals[1].name, Some("Abenakiite-(Ce)".to_string()));
+}
+
+#[tokio::test]
+async fn test_geomaterials_stream_prefetches_requested_pages() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(|request: &wiremock::Request| {
+            let page: u32 = request
+                .url
+                .query_pairs()
+                .find(|(key, _)| key == "page")
+                .map_or(1, |(_, page)| page.parse().unwrap());
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "count": 100,
+                "next": format!("http://example.com/geomaterials/?page={}", page + 1),
+                "previous": null,
+                "results": [{"id": page, "name": "Chalcopyrite"}]
+            }))
+        })
+        .mount(&mock_server)
+        .await;
+
+    for prefetch in [1, 3] {
+        let before = mock_server.received_requests().await.unwrap().len();
+        let mut minerals = client
+            .geomaterials_stream(GeomaterialsQuery::new())
+            .prefetch(prefetch);
+        minerals.next().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let requests = mock_server.received_requests().await.unwrap();
+        assert_eq!(requests.len() - before, 1 + prefetch, "prefetch {prefetch}");
+    }
+
+    // Pages beyond those holding the requested items are never fetched
+    let before = mock_server.received_requests().await.unwrap().len();
+    let minerals: Vec<_> = client
+        .geomaterials_stream(GeomaterialsQuery::new())
+        .max_items(2)
+        .prefetch(3)
+        .try_collect()
+        .await
+        .expect("Stream failed");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(minerals.len(), 2);
+    let requests = mock_server.received_requests().await.unwrap();
+    assert_eq!(requests.len() - before, 2);
+}
+
+#[tokio::test]
+async fn test_geomaterials_stream_stops_on_error() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("Server error"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 2,
+            "next": "http://example.com/geomaterials/?page=2",
+            "previous": null,
+            "results": [{"id": 1, "name": "Quartz"}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut stream = client.geomaterials_stream(GeomaterialsQuery::new());
+
+    assert_eq!(stream.next().await.unwrap().unwrap().id, 1);
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(mindat_rs::MindatError::Api { status: 500, .. }))
+    ));
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_localities_stream_follows_cursor() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/localities/"))
+        .and(query_param("cursor", "abc"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "next": null,
+            "previous": "http://example.com/localities/?country=Brazil",
+            "results": [{"id": 102, "txt": "Ouro Preto"}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/localities/"))
+        .and(query_param("country", "Brazil"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "next": "http://example.com/localities/?country=Brazil&cursor=abc",
+            "previous": null,
+            "results": [
+                {"id": 100, "txt": "Minas Gerais"},
+                {"id": 101, "txt": "Bahia"}
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut stream = client.localities_stream(LocalitiesQuery::new().country("Brazil"));
+
+    assert_eq!(stream.next().await.unwrap().unwrap().id, 100);
+    assert_eq!(stream.resume_cursor(), None);
+    assert_eq!(stream.next().await.unwrap().unwrap().id, 101);
+    assert_eq!(stream.resume_cursor(), Some("abc".to_string()));
+    assert_eq!(stream.next().await.unwrap().unwrap().id, 102);
+    assert!(stream.next().await.is_none());
+    assert_eq!(stream.resume_cursor(), None);
+    assert_eq!(stream.pages_fetched(), 2);
+}
+
+#[tokio::test]
+async fn test_localities_stream_falls_back_to_pages() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/localities/"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("Server error"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/localities/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "next": "http://example.com/localities/?page=2",
+            "previous": null,
+            "results": [{"id": 100, "txt": "Minas Gerais"}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut stream = client.localities_stream(LocalitiesQuery::new());
+
+    assert_eq!(stream.next().await.unwrap().unwrap().id, 100);
+    assert!(stream.next().await.unwrap().is_err());
+    assert_eq!(stream.resume_page(), Some(2));
+    assert_eq!(stream.resume_cursor(), None);
+}
+
+fn fast_retry_policy() -> RetryPolicy {
+    RetryPolicy::new()
+        .max_attempts(3)
+        .base_delay(Duration::from_millis(1))
+        .max_delay(Duration::from_millis(10))
+}
+
+#[tokio::test]
+async fn test_retry_recovers_from_server_error() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .retry_policy(fast_retry_policy())
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let result = client.geomaterial(3337).await.expect("Request failed");
+    assert_eq!(result.id, 3337);
+}
+
+#[tokio::test]
+async fn test_retry_honors_retry_after() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .retry_policy(fast_retry_policy().max_delay(Duration::from_secs(1)))
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"count": 1})))
+        .mount(&mock_server)
+        .await;
+
+    let result = client.photocount().await.expect("Request failed");
+    assert_eq!(result["count"], 1);
+}
+
+#[tokio::test]
+async fn test_retry_reports_attempts() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .retry_policy(fast_retry_policy())
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(ResponseTemplate::new(502).set_body_string("Bad gateway"))
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    let err = client.photocount().await.unwrap_err();
+    match err {
+        mindat_rs::MindatError::RetriesExhausted { attempts, source } => {
+            assert_eq!(attempts, 3);
+            assert!(matches!(
+                *source,
+                mindat_rs::MindatError::Api { status: 502, .. }
+            ));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_terminal_error_after_retry_is_not_wrapped() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .retry_policy(fast_retry_policy())
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/1/"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/1/"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&mock_server)
+        .await;
+
+    let err = client.geomaterial(1).await.unwrap_err();
+    assert!(
+        matches!(err, mindat_rs::MindatError::NotFound { .. }),
+        "unexpected error: {err:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_rate_limit_shared_across_clones() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .rate_limit(RateLimit::concurrency(1))
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"count": 1}))
+                .set_delay(Duration::from_millis(100)),
+        )
+        .expect(3)
+        .mount(&mock_server)
+        .await;
+
+    let start = std::time::Instant::now();
+    let tasks: Vec<_> = (0..3)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.photocount().await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().expect("Request failed");
+    }
+
+    // One request at a time across all clones
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn test_rate_limit_slot_is_free_during_retry_backoff() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .rate_limit(RateLimit::concurrency(1))
+        .retry_policy(
+            RetryPolicy::new()
+                .max_attempts(2)
+                .base_delay(Duration::from_millis(500))
+                .jitter(0.0),
+        )
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/1/"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"count": 1})))
+        .mount(&mock_server)
+        .await;
+
+    let failing = tokio::spawn({
+        let client = client.clone();
+        async move { client.geomaterial(1).await }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The failing request is waiting to retry and does not hold the slot
+    let start = std::time::Instant::now();
+    client.photocount().await.expect("Request failed");
+    assert!(start.elapsed() < Duration::from_millis(300));
+    assert!(failing.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn test_builder_custom_headers() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .user_agent("mindat-rs-tests/1.0")
+        .header("X-Request-Source", "integration-test")
+        .expect("Invalid header")
+        .connect_timeout(Duration::from_secs(2))
+        .read_timeout(Duration::from_secs(5))
+        .pool_max_idle_per_host(1)
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .and(header("User-Agent", "mindat-rs-tests/1.0"))
+        .and(header("X-Request-Source", "integration-test"))
+        .and(header("Accept", "application/json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"count": 1})))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    client.photocount().await.expect("Request failed");
+}
+
+#[tokio::test]
+async fn test_builder_default_user_agent() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .and(|req: &wiremock::Request| {
+            // The header matcher splits on commas, so compare the raw value
+            req.headers.get("User-Agent").map(|v| v.as_bytes())
+                == Some(mindat_rs::DEFAULT_USER_AGENT.as_bytes())
+        })
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"count": 1})))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    client.photocount().await.expect("Request failed");
+}
+
+#[tokio::test]
+async fn test_builder_timeout() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .timeout(Duration::from_millis(50))
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/photo-count/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"count": 1}))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .mount(&mock_server)
+        .await;
+
+    match client.photocount().await {
+        Err(mindat_rs::MindatError::Request(e)) => assert!(e.is_timeout()),
+        other => panic!("expected timeout, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_cache_serves_repeated_requests() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .cache(CacheConfig::new(10))
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/locality-type/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 1,
+            "next": null,
+            "previous": null,
+            "results": [{"lt_id": 1, "lt_text": "Mine"}]
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    for _ in 0..3 {
+        client.locality_types(None).await.expect("Request failed");
+    }
+    client.invalidate_cache("/locality-type/");
+    client.locality_types(None).await.expect("Request failed");
+
+    let stats = client.cache_stats().expect("cache configured");
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.misses, 2);
+}
+
+#[tokio::test]
+async fn test_cache_revalidates_expired_entries() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .cache(CacheConfig::new(10).ttl(Duration::from_millis(50)))
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .and(header("If-None-Match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"v1\"")
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    client.geomaterial(3337).await.expect("Request failed");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let mineral = client.geomaterial(3337).await.expect("Request failed");
+
+    assert_eq!(mineral.name, Some("Quartz".to_string()));
+    assert_eq!(client.cache_stats().unwrap().revalidations, 1);
+}
+
+#[tokio::test]
+async fn test_cache_refetches_when_revalidated_entry_is_gone() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .cache(CacheConfig::new(10).ttl(Duration::from_millis(50)))
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .and(header("If-None-Match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304).set_delay(Duration::from_millis(200)))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"v1\"")
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    client.geomaterial(3337).await.expect("Request failed");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let (mineral, ()) = futures::join!(client.geomaterial(3337), async {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        client.clear_cache();
+    });
+
+    assert_eq!(mineral.unwrap().name, Some("Quartz".to_string()));
+}
+
+/// Tags requests and redirects them to a mirror.
+#[derive(Debug)]
+struct MirrorMiddleware {
+    mirror: String,
+}
+
+impl Middleware for MirrorMiddleware {
+    fn before_request(&self, request: &mut TransportRequest) -> Result<Option<TransportResponse>> {
+        let mirror = url::Url::parse(&self.mirror).unwrap();
+        request.url.set_port(mirror.port()).unwrap();
+        request
+            .headers
+            .insert("X-Correlation-Id", "abc-123".parse().unwrap());
+        Ok(None)
+    }
+}
+
+/// Answers photo count requests without contacting the API.
+#[derive(Debug)]
+struct CannedPhotoCount;
+
+impl Middleware for CannedPhotoCount {
+    fn before_request(&self, request: &mut TransportRequest) -> Result<Option<TransportResponse>> {
+        if !request.url.path().ends_with("/photo-count/") {
+            return Ok(None);
+        }
+        Ok(Some(TransportResponse {
+            status: reqwest::StatusCode::OK,
+            headers: reqwest::header::HeaderMap::new(),
+            body: r#"{"count": 42}"#.into(),
+            url: request.url.clone(),
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_middleware_rewrites_requests() {
+    let mirror = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url("http://127.0.0.1:9/")
+        .middleware(MirrorMiddleware {
+            mirror: mirror.uri(),
+        })
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .and(header("X-Correlation-Id", "abc-123"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .expect(1)
+        .mount(&mirror)
+        .await;
+
+    let mineral = client.geomaterial(3337).await.expect("Request failed");
+    assert_eq!(mineral.name, Some("Quartz".to_string()));
+}
+
+#[tokio::test]
+async fn test_middleware_short_circuits() {
+    let client = MindatClient::builder()
+        .base_url("http://127.0.0.1:9/")
+        .middleware(CannedPhotoCount)
+        .build()
+        .expect("Failed to build client");
+
+    let count = client.photocount().await.expect("Request failed");
+    assert_eq!(count["count"], 42);
+}
+
+/// Records the bodies of failed responses.
+#[derive(Debug, Default)]
+struct FailureLog(std::sync::Mutex<Vec<String>>);
+
+impl Middleware for FailureLog {
+    fn after_response(
+        &self,
+        _request: &TransportRequest,
+        response: &mut TransportResponse,
+    ) -> Result<()> {
+        if !response.status.is_success() {
+            let body = String::from_utf8_lossy(&response.body).into_owned();
+            self.0.lock().unwrap().push(body);
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_middleware_sees_failed_responses() {
+    let mock_server = MockServer::start().await;
+    let log = std::sync::Arc::new(FailureLog::default());
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .middleware(log.clone())
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/1/"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("database unavailable"))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(403).set_body_string("token revoked"))
+        .mount(&mock_server)
+        .await;
+
+    assert!(client.geomaterial(1).await.is_err());
+    let mut items = client.geomaterials_items(GeomaterialsQuery::new());
+    assert!(items.next().await.unwrap().is_err());
+    assert_eq!(
+        *log.0.lock().unwrap(),
+        vec!["database unavailable", "token revoked"]
+    );
+}
+
+#[tokio::test]
+async fn test_request_unmodeled_endpoint() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Article {
+        id: i32,
+        title: String,
+    }
+
+    Mock::given(method("GET"))
+        .and(path("/articles/"))
+        .and(query_param("q", "quartz"))
+        .and(query_param("page", "2"))
+        .and(header("Authorization", "Token test-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 1,
+            "next": null,
+            "previous": null,
+            "results": [{"id": 7, "title": "Quartz twins"}]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let page: mindat_rs::PaginatedResponse<Article> = client
+        .request("/articles/", &[("q", "quartz"), ("page", "2")])
+        .await
+        .expect("Request failed");
+    assert_eq!(page.results[0].id, 7);
+    assert_eq!(page.results[0].title, "Quartz twins");
+}
+
+#[tokio::test]
+async fn test_request_raw_maps_errors() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "new_field": [1, 2]})),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let raw = client
+        .request_raw("/geomaterials/3337/", &())
+        .await
+        .expect("Request failed");
+    assert_eq!(raw["new_field"], serde_json::json!([1, 2]));
+
+    let err = client
+        .request_raw("/no-such-endpoint/", &())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, mindat_rs::MindatError::NotFound { .. }));
+}
+
+#[tokio::test]
+async fn test_deserialization_error_reports_field_path() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 2,
+            "next": null,
+            "previous": null,
+            "results": [
+                {"id": 1, "name": "Quartz"},
+                {"id": 2, "name": "Calcite", "discovery_year": {"year": 1845}}
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let err = client
+        .geomaterials(GeomaterialsQuery::new().name("quartz"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), mindat_rs::ErrorKind::Decode);
+    assert!(!err.is_retryable());
+    match err {
+        mindat_rs::MindatError::Deserialization {
+            url, path, snippet, ..
+        } => {
+            assert!(url.ends_with("/geomaterials/?name=quartz"));
+            assert_eq!(path, "results[1].discovery_year");
+            assert!(snippet.contains("Calcite"));
+        }
+        other => panic!("expected a deserialization error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_error_response_details() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/1/"))
+        .respond_with(
+            ResponseTemplate::new(503)
+                .insert_header("X-Request-Id", "req-123")
+                .set_body_string("x".repeat(5000)),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/2/"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "12"))
+        .mount(&mock_server)
+        .await;
+
+    let err = client.geomaterial(1).await.unwrap_err();
+    assert_eq!(err.kind(), mindat_rs::ErrorKind::Api);
+    assert!(err.is_retryable());
+    let details = err.response().expect("missing response details");
+    assert_eq!(details.status, 503);
+    assert_eq!(details.request_id.as_deref(), Some("req-123"));
+    assert!(details.url.ends_with("/geomaterials/1/"));
+    match &err {
+        mindat_rs::MindatError::Api { message, .. } => assert!(message.len() < 1000),
+        other => panic!("expected an API error, got {:?}", other),
+    }
+
+    let err = client.geomaterial(2).await.unwrap_err();
+    assert_eq!(err.kind(), mindat_rs::ErrorKind::RateLimited);
+    assert!(err.is_retryable());
+    assert!(matches!(
+        err,
+        mindat_rs::MindatError::RateLimited {
+            retry_after: Some(d),
+            ..
+        } if d == Duration::from_secs(12)
+    ));
+}
+
+#[tokio::test]
+async fn test_cloudflare_challenge_is_detected() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/1/"))
+        .respond_with(
+            ResponseTemplate::new(403)
+                .insert_header("cf-mitigated", "challenge")
+                .insert_header("cf-ray", "8a1b2c3d4e5f-AMS")
+                .set_body_raw(
+                    "<!DOCTYPE html><html><head><title>Just a moment...</title></head></html>",
+                    "text/html; charset=UTF-8",
+                ),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let err = client.geomaterial(1).await.unwrap_err();
+    assert_eq!(err.kind(), mindat_rs::ErrorKind::Blocked);
+    assert!(matches!(
+        err,
+        mindat_rs::MindatError::Html {
+            page: mindat_rs::HtmlPage::CloudflareChallenge,
+            ..
+        }
+    ));
+    assert_eq!(
+        err.response().unwrap().request_id.as_deref(),
+        Some("8a1b2c3d4e5f-AMS")
+    );
+    assert!(err.to_string().contains("Cloudflare"));
+}
+
+#[tokio::test]
+async fn test_html_error_page_is_detected() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    // A 200 HTML page would otherwise fail as a JSON parse error
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/1/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><head><title>Scheduled maintenance</title></head><body>...</body></html>",
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/2/"))
+        .respond_with(ResponseTemplate::new(403).set_body_raw(
+            "<html><head><title>Attention Required! | Cloudflare</title></head>\
+             <body><div id=\"cf-error-details\">Sorry, you have been blocked</div></body></html>",
+            "text/html",
+        ))
+        .mount(&mock_server)
+        .await;
+
+    let err = client.geomaterial(1).await.unwrap_err();
+    match &err {
+        mindat_rs::MindatError::Html {
+            page: mindat_rs::HtmlPage::Other { title },
+            ..
+        } => assert_eq!(title.as_deref(), Some("Scheduled maintenance")),
+        other => panic!("expected an HTML page error, got {:?}", other),
+    }
+
+    let err = client.geomaterial(2).await.unwrap_err();
+    assert!(matches!(
+        err,
+        mindat_rs::MindatError::Html {
+            page: mindat_rs::HtmlPage::CloudflareBlock,
+            ..
+        }
+    ));
+    assert!(!err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_client_through_api_trait() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .and(header("Authorization", "Token test-token"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials-search/"))
+        .and(query_param("q", "quartz"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([{"id": 3337}])))
+        .mount(&mock_server)
+        .await;
+
+    async fn lookup(api: &dyn MindatApi) -> Result<Option<String>> {
+        let hits = api.geomaterials_search("quartz", None).await?;
+        let id = hits[0]["id"].as_i64().unwrap() as i32;
+        Ok(api.geomaterial(id).await?.name)
+    }
+
+    assert_eq!(lookup(&client).await.unwrap().as_deref(), Some("Quartz"));
+    let shared: std::sync::Arc<dyn MindatApi> = std::sync::Arc::new(client);
+    assert_eq!(lookup(&shared).await.unwrap().as_deref(), Some("Quartz"));
+}
+
+#[tokio::test]
+async fn test_coalesces_identical_concurrent_requests() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .coalesce_requests()
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"}))
+                .set_delay(Duration::from_millis(200)),
+        )
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let mut other_token = client.clone();
+    other_token.set_token("other-token");
+    let (shared, other) = futures::join!(
+        futures::future::join_all((0..5).map(|_| client.geomaterial(3337))),
+        other_token.geomaterial(3337),
+    );
+    for quartz in shared {
+        assert_eq!(quartz.unwrap().name, Some("Quartz".to_string()));
+    }
+    assert_eq!(other.unwrap().id, 3337);
+}
+
+#[tokio::test]
+async fn test_coalesced_failure_reaches_every_caller() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .base_url(mock_server.uri())
+        .coalesce_requests()
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("not json")
+                .set_delay(Duration::from_millis(200)),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let results = futures::future::join_all((0..3).map(|_| client.geomaterial(3337))).await;
+    for result in results {
+        assert_eq!(result.unwrap_err().kind(), mindat_rs::ErrorKind::Decode);
+    }
+}
+
+#[tokio::test]
+async fn test_response_metadata() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .retry_policy(
+            RetryPolicy::new()
+                .base_delay(Duration::from_millis(1))
+                .jitter(0.0),
+        )
+        .cache(CacheConfig::new(10))
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("X-Request-Id", "req-42")
+                .insert_header("X-RateLimit-Remaining", "99")
+                .set_body_json(serde_json::json!({"id": 3337, "name": "Quartz"})),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let response = client
+        .geomaterial_with_meta(3337)
+        .await
+        .expect("Request failed");
+    assert_eq!(response.body.name, Some("Quartz".to_string()));
+    assert_eq!(response.status, reqwest::StatusCode::OK);
+    assert_eq!(response.attempts, 2);
+    assert!(!response.cached);
+    assert_eq!(response.request_id(), Some("req-42"));
+    assert_eq!(response.rate_limit_remaining(), Some(99));
+    assert_eq!(response.url.path(), "/geomaterials/3337/");
+
+    let cached = client
+        .geomaterial_with_meta(3337)
+        .await
+        .expect("Request failed");
+    assert!(cached.cached);
+    assert_eq!(cached.attempts, 0);
+    assert_eq!(cached.request_id(), Some("req-42"));
+}
+
+#[tokio::test]
+async fn test_compressed_response_is_decoded() {
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let (mock_server, client) = setup_mock_client().await;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(br#"{"id": 3337, "name": "Quartz"}"#)
+        .unwrap();
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .and(header_regex("Accept-Encoding", "gzip"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Encoding", "gzip")
+                .insert_header("Content-Type", "application/json")
+                .set_body_bytes(encoder.finish().unwrap()),
+        )
+        .mount(&mock_server)
+        .await;
+
+    let mineral = client.geomaterial(3337).await.expect("Request failed");
+    assert_eq!(mineral.name, Some("Quartz".to_string()));
+}
+
+#[tokio::test]
+async fn test_geomaterials_items_follows_next() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 3,
+            "next": null,
+            "previous": "http://example.com/geomaterials/?page_size=2",
+            "results": [
+                {"id": 3, "name": "Cuprite"}
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .and(query_param("page_size", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 3,
+            "results": [
+                {"id": 1, "name": "Chalcopyrite", "mindat_formula": "CuFeS<sub>2</sub>"},
+                {"id": 2, "name": "Covellite"}
+            ],
+            "next": "http://example.com/geomaterials/?page=2&page_size=2",
+            "previous": null
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let mut stream = client.geomaterials_items(GeomaterialsQuery::new().page_size(2));
+    let mut ids = Vec::new();
+    while let Some(mineral) = stream.next().await {
+        ids.push(mineral.expect("Stream failed").id);
+    }
+
+    assert_eq!(ids, vec![1, 2, 3]);
+    assert_eq!(stream.total_count(), Some(3));
+    assert_eq!(stream.pages_fetched(), 2);
+    assert_eq!(stream.items_yielded(), 3);
+}
+
+#[tokio::test]
+async fn test_geomaterials_items_reports_field_path() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 2,
+            "next": "http://example.com/geomaterials/?page=2",
+            "previous": null,
+            "results": [
+                {"id": 1, "name": "Quartz"},
+                {"id": 2, "name": "Calcite", "discovery_year": {"year": 1845}}
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut stream = client.geomaterials_items(GeomaterialsQuery::new());
+
+    assert_eq!(stream.next().await.unwrap().unwrap().id, 1);
+    match stream.next().await {
+        Some(Err(mindat_rs::MindatError::Deserialization { path, snippet, .. })) => {
+            assert_eq!(path, "results[1].discovery_year");
+            assert!(snippet.contains("Calcite"));
+        }
+        other => panic!("expected a deserialization error, got {:?}", other),
+    }
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_localities_items_maps_error_responses() {
+    let (mock_server, client) = setup_mock_client().await;
+
+    Mock::given(method("GET"))
+        .and(path("/localities/"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&mock_server)
+        .await;
+
+    let mut stream = client.localities_items(LocalitiesQuery::new());
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(mindat_rs::MindatError::AuthenticationRequired { .. }))
+    ));
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_strict_mode_rejects_unknown_fields() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .decode_mode(DecodeMode::Strict)
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/3337/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": 3337,
+            "name": "Quartz",
+            "colour_code": "white"
+        })))
+        .mount(&mock_server)
+        .await;
+
+    match client.geomaterial(3337).await.unwrap_err() {
+        mindat_rs::MindatError::Deserialization { path, source, .. } => {
+            assert_eq!(path, ".");
+            assert!(source.to_string().contains("unknown field `colour_code`"));
+        }
+        other => panic!("expected a deserialization error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_lenient_mode_skips_bad_records() {
+    let mock_server = MockServer::start().await;
+    let client = MindatClient::builder()
+        .token("test-token")
+        .base_url(mock_server.uri())
+        .decode_mode(DecodeMode::Lenient)
+        .build()
+        .expect("Failed to build client");
+
+    Mock::given(method("GET"))
+        .and(path("/geomaterials/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "count": 3,
+            "next": null,
+            "previous": null,
+            "results": [
+                {"id": 1, "name": "Quartz", "colour_code": "white"},
+                {"id": 2, "name": "Calcite", "discovery_year": {"year": 1845}},
+                {"id": 3, "name": "Fluorite"}
+            ]
+        })))
+        .mount(&mock_server)
+        .await;
+
+    let response = client
+        .geomaterials_with_meta(GeomaterialsQuery::new())
+        .await
+        .expect("Request failed");
+    let ids: Vec<i32> = response.body.results.iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![1, 3]);
+    assert_eq!(response.body.results[0].extra["colour_code"], "white");
+    assert_eq!(response.skipped.len(), 1);
+    assert_eq!(response.skipped[0].id, Some(2));
+    assert_eq!(response.skipped[0].path, "results[1].discovery_year");
+
+    let mut stream = client.geomaterials_items(GeomaterialsQuery::new());
+    let mut ids = Vec::new();
+    while let Some(mineral) = stream.next().await {
+        ids.push(mineral.expect("Stream failed").id);
+    }
+    assert_eq!(ids, vec![1, 3]);
+    assert_eq!(stream.skipped(), response.skipped.as_slice());
+}
@@ -0,0 +1,219 @@
+//! Tests for the tracing spans and metrics reported for requests.
+
+#![cfg(all(feature = "tracing", feature = "metrics", feature = "testing"))]
+
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+    SharedString, Unit,
+};
+use mindat_rs::testing::FakeMindat;
+use mindat_rs::{GeomaterialsQuery, RetryPolicy};
+use reqwest::StatusCode;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+
+/// Fields of a span, formatted with `Debug`.
+type Fields = BTreeMap<String, String>;
+
+struct FieldsVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldsVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+/// A subscriber that keeps the fields of every `mindat.request` span.
+#[derive(Clone, Default)]
+struct SpanRecorder {
+    next_id: Arc<AtomicU64>,
+    spans: Arc<Mutex<HashMap<u64, Fields>>>,
+}
+
+impl SpanRecorder {
+    fn requests(&self) -> Vec<Fields> {
+        let spans = self.spans.lock().unwrap();
+        let mut ids: Vec<_> = spans.keys().copied().collect();
+        ids.sort();
+        ids.iter().map(|id| spans[id].clone()).collect()
+    }
+}
+
+impl Subscriber for SpanRecorder {
+    fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        if span.metadata().name() == "mindat.request" {
+            let mut fields = Fields::new();
+            span.record(&mut FieldsVisitor(&mut fields));
+            self.spans.lock().unwrap().insert(id, fields);
+        }
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(fields) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(&mut FieldsVisitor(fields));
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Values reported for one metric.
+#[derive(Default)]
+struct Values(Mutex<Vec<f64>>);
+
+impl CounterFn for Values {
+    fn increment(&self, value: u64) {
+        self.0.lock().unwrap().push(value as f64);
+    }
+
+    fn absolute(&self, value: u64) {
+        self.0.lock().unwrap().push(value as f64);
+    }
+}
+
+impl HistogramFn for Values {
+    fn record(&self, value: f64) {
+        self.0.lock().unwrap().push(value);
+    }
+}
+
+/// A recorder that keeps every value reported for counters and histograms,
+/// by name and labels.
+#[derive(Default)]
+struct MetricsRecorder {
+    metrics: Mutex<HashMap<String, Arc<Values>>>,
+}
+
+impl MetricsRecorder {
+    fn key(key: &Key) -> String {
+        let mut labels: Vec<_> = key
+            .labels()
+            .map(|label| format!("{}={}", label.key(), label.value()))
+            .collect();
+        labels.sort();
+        format!("{}{{{}}}", key.name(), labels.join(","))
+    }
+
+    fn values(&self, key: &Key) -> Arc<Values> {
+        self.metrics
+            .lock()
+            .unwrap()
+            .entry(Self::key(key))
+            .or_default()
+            .clone()
+    }
+
+    fn get(&self, key: &str) -> Vec<f64> {
+        match self.metrics.lock().unwrap().get(key) {
+            Some(values) => values.0.lock().unwrap().clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Recorder for &'static MetricsRecorder {
+    fn describe_counter(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_gauge(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn describe_histogram(&self, _key: KeyName, _unit: Option<Unit>, _description: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _metadata: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.values(key))
+    }
+
+    fn register_gauge(&self, _key: &Key, _metadata: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, key: &Key, _metadata: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(self.values(key))
+    }
+}
+
+/// The process-wide metrics recorder, installed on first use.
+fn metrics_recorder() -> &'static MetricsRecorder {
+    static RECORDER: OnceLock<&'static MetricsRecorder> = OnceLock::new();
+    RECORDER.get_or_init(|| {
+        let recorder: &'static MetricsRecorder = Box::leak(Box::default());
+        metrics::set_global_recorder(recorder).expect("Recorder already installed");
+        recorder
+    })
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_request_span_and_metrics() {
+    let metrics = metrics_recorder();
+    let spans = SpanRecorder::default();
+    let _guard = tracing::subscriber::set_default(spans.clone());
+
+    let fake = FakeMindat::sample();
+    let client = fake
+        .client_builder()
+        .retry_policy(RetryPolicy::new())
+        .build()
+        .unwrap();
+
+    let page = client
+        .geomaterials(GeomaterialsQuery::new().name("quartz").page_size(2))
+        .await
+        .unwrap();
+    assert!(!page.results.is_empty());
+    fake.fail_next(StatusCode::SERVICE_UNAVAILABLE);
+    client.geomaterial(3337).await.unwrap();
+
+    let requests = spans.requests();
+    assert_eq!(requests.len(), 2);
+    let list = &requests[0];
+    assert_eq!(list["endpoint"], "geomaterials/");
+    assert_eq!(list["query"], "name=quartz&page_size=2");
+    assert_eq!(list["status"], "200");
+    assert_eq!(list["attempts"], "1");
+    assert_eq!(list["cached"], "false");
+    assert_eq!(list["result_count"], page.results.len().to_string());
+    assert!(list.contains_key("latency_ms"));
+    let detail = &requests[1];
+    assert_eq!(detail["endpoint"], "geomaterials/{id}/");
+    assert_eq!(detail["attempts"], "2");
+    assert!(!detail.contains_key("result_count"));
+
+    assert_eq!(
+        metrics.get("mindat_requests_total{cached=false,endpoint=geomaterials/,status=200}"),
+        [1.0]
+    );
+    assert_eq!(
+        metrics.get("mindat_requests_total{cached=false,endpoint=geomaterials/{id}/,status=200}"),
+        [1.0]
+    );
+    assert_eq!(
+        metrics
+            .get("mindat_request_duration_seconds{endpoint=geomaterials/}")
+            .len(),
+        1
+    );
+    assert_eq!(
+        metrics.get("mindat_request_results{endpoint=geomaterials/}"),
+        [page.results.len() as f64]
+    );
+    assert_eq!(
+        metrics.get("mindat_request_retries_total{endpoint=geomaterials/{id}/}"),
+        [1.0]
+    );
+}
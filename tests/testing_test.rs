@@ -0,0 +1,125 @@
+//! Tests for the fake Mindat API.
+
+#![cfg(feature = "testing")]
+
+use futures::TryStreamExt;
+use mindat_rs::testing::FakeMindat;
+use mindat_rs::{
+    GeomaterialsQuery, ImaMineralsQuery, LocalitiesQuery, MindatClient, MindatError, RetryPolicy,
+};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_filters_and_page_pagination() {
+    let fake = FakeMindat::sample().token("test-token");
+    let client = fake.client_builder().build().unwrap();
+
+    let query = GeomaterialsQuery::new()
+        .with_elements("Fe,S")
+        .ima_approved(true)
+        .page_size(1);
+    let page = client.geomaterials(query).await.unwrap();
+    assert_eq!(page.count, Some(2));
+    assert_eq!(page.results.len(), 1);
+    assert!(page.has_next());
+
+    let names: Vec<_> = client
+        .geomaterials_stream(
+            GeomaterialsQuery::new()
+                .hardness_range(6.0, 7.0)
+                .page_size(2),
+        )
+        .map_ok(|mineral| mineral.name.unwrap())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(names, vec!["Quartz", "Pyrite", "Amethyst"]);
+
+    let err = client
+        .geomaterials(GeomaterialsQuery::new().page(99))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MindatError::NotFound { .. }));
+}
+
+#[tokio::test]
+async fn test_localities_follow_cursor() {
+    let fake = FakeMindat::sample();
+    let client = fake.client_builder().build().unwrap();
+
+    let first = client
+        .localities(LocalitiesQuery::new().page_size(2))
+        .await
+        .unwrap();
+    assert_eq!(first.next_cursor().as_deref(), Some("o2"));
+
+    let ids: Vec<_> = client
+        .localities_stream(LocalitiesQuery::new().country("usa").page_size(1))
+        .map_ok(|locality| locality.id)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1992, 2690]);
+}
+
+#[tokio::test]
+async fn test_token_is_checked_except_for_ima_minerals() {
+    let fake = FakeMindat::sample().token("test-token");
+    let client = MindatClient::builder()
+        .transport(fake.clone())
+        .build()
+        .unwrap();
+
+    let err = client.geomaterial(3337).await.unwrap_err();
+    assert!(matches!(err, MindatError::AuthenticationRequired { .. }));
+
+    let minerals = client
+        .minerals_ima(ImaMineralsQuery::new().search("py"))
+        .await
+        .unwrap();
+    assert_eq!(minerals.results.len(), 2);
+
+    let client = fake.client_builder().build().unwrap();
+    assert_eq!(client.geomaterial(3337).await.unwrap().id, 3337);
+    assert_eq!(
+        client.locality_status(2).await.unwrap().ls_text,
+        "Abandoned mine"
+    );
+    assert!(client.dana8_groups().await.unwrap()["results"].is_array());
+    assert!(matches!(
+        client.geomaterial(1).await.unwrap_err(),
+        MindatError::NotFound { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_rate_limit_responses() {
+    let fake = FakeMindat::sample().quota(1);
+    let client = fake.client_builder().build().unwrap();
+
+    client.geomaterial(3337).await.unwrap();
+    let err = client.geomaterial(3337).await.unwrap_err();
+    match err {
+        MindatError::RateLimited { retry_after, .. } => {
+            assert_eq!(retry_after, Some(Duration::from_secs(1)));
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+    assert_eq!(fake.requests().len(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_injected_failure_is_retried() {
+    let fake = FakeMindat::sample();
+    let client = fake
+        .client_builder()
+        .retry_policy(RetryPolicy::new())
+        .build()
+        .unwrap();
+
+    fake.fail_next(StatusCode::SERVICE_UNAVAILABLE);
+    let quartz = client.geomaterial(3337).await.unwrap();
+    assert_eq!(quartz.name.as_deref(), Some("Quartz"));
+    assert_eq!(fake.requests().len(), 2);
+}
@@ -0,0 +1,118 @@
+//! Tests for custom transports.
+
+use futures::future::BoxFuture;
+use mindat_rs::transport::{Transport, TransportRequest, TransportResponse};
+use mindat_rs::{GeomaterialsQuery, MindatClient, MindatError, Result, RetryPolicy};
+use reqwest::StatusCode;
+use reqwest::header::{AUTHORIZATION, HeaderMap};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Serves queued responses and records every request it receives.
+#[derive(Debug, Default)]
+struct MockTransport {
+    responses: Mutex<VecDeque<(u16, &'static str)>>,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl MockTransport {
+    fn new(responses: Vec<(u16, &'static str)>) -> Arc<Self> {
+        Arc::new(Self {
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::default(),
+        })
+    }
+
+    fn requests(&self) -> Vec<TransportRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, Result<TransportResponse>> {
+        let url = request.url.clone();
+        self.requests.lock().unwrap().push(request);
+        let next = self.responses.lock().unwrap().pop_front();
+
+        Box::pin(async move {
+            let (status, body) =
+                next.ok_or_else(|| MindatError::Transport("no response".into()))?;
+            Ok(TransportResponse {
+                status: StatusCode::from_u16(status).unwrap(),
+                headers: HeaderMap::new(),
+                body: body.into(),
+                url,
+            })
+        })
+    }
+}
+
+fn client_with(transport: &Arc<MockTransport>) -> MindatClient {
+    MindatClient::builder()
+        .token("test-token")
+        .base_url("https://mindat.test/v1/")
+        .transport(transport.clone())
+        .retry_policy(
+            RetryPolicy::new()
+                .max_attempts(3)
+                .base_delay(Duration::from_millis(1))
+                .jitter(0.0),
+        )
+        .build()
+        .expect("Failed to build client")
+}
+
+#[tokio::test]
+async fn test_custom_transport_receives_requests() {
+    let transport = MockTransport::new(vec![(200, r#"{"id": 3337, "name": "Quartz"}"#)]);
+    let client = client_with(&transport);
+
+    let mineral = client.geomaterial(3337).await.expect("Request failed");
+    assert_eq!(mineral.name, Some("Quartz".to_string()));
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, reqwest::Method::GET);
+    assert_eq!(
+        requests[0].url.as_str(),
+        "https://mindat.test/v1/geomaterials/3337/"
+    );
+    assert_eq!(requests[0].headers[AUTHORIZATION], "Token test-token");
+}
+
+#[tokio::test]
+async fn test_custom_transport_is_retried() {
+    let transport = MockTransport::new(vec![
+        (503, "unavailable"),
+        (
+            200,
+            r#"{"count": 0, "next": null, "previous": null, "results": []}"#,
+        ),
+    ]);
+    let client = client_with(&transport);
+
+    let result = client
+        .geomaterials(GeomaterialsQuery::new().name("quartz"))
+        .await
+        .expect("Request failed");
+
+    assert!(result.results.is_empty());
+    assert_eq!(transport.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_custom_transport_errors_are_retried() {
+    let transport = MockTransport::new(vec![]);
+    let client = client_with(&transport);
+
+    let err = client.geomaterial(1).await.unwrap_err();
+
+    assert!(err.is_retryable());
+    assert!(matches!(
+        err,
+        MindatError::RetriesExhausted { attempts: 3, ref source }
+            if matches!(**source, MindatError::Transport(_))
+    ));
+    assert_eq!(transport.requests().len(), 3);
+}